@@ -0,0 +1,133 @@
+use super::super::db::mongo::DB;
+use super::checks::is_guild_admin;
+use crate::{Context, Error};
+use log::{error, info};
+use poise::serenity_prelude as serenity;
+use serenity::utils::Color;
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    check = "is_guild_admin",
+    subcommands("role")
+)]
+pub async fn admin(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+#[poise::command(slash_command, subcommands("role_set", "role_show", "role_clear"))]
+pub async fn role(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set the role allowed to manage pools from this guild
+#[poise::command(slash_command, guild_only, rename = "set")]
+pub async fn role_set(
+    ctx: Context<'_>,
+    #[description = "Admin Role"] role: serenity::Role,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db
+        .add_admin_role(
+            &guild_id,
+            &role.id.to_string(),
+            &ctx.author().id.to_string(),
+        )
+        .await
+    {
+        Ok(_) => {
+            info!("Set {} as admin role on guild {}", role.id, guild_id);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Admin Role")
+                        .color(Color::DARK_GREEN)
+                        .description(format!("<@&{}> is now the admin role", role.id))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to set admin role on {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Admin Role")
+                        .color(Color::RED)
+                        .description(format!("Failed to set the admin role:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Show the role allowed to manage pools from this guild
+#[poise::command(slash_command, guild_only, rename = "show")]
+pub async fn role_show(ctx: Context<'_>) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db.list_admin_role(&guild_id).await {
+        Ok(role) => {
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Admin Role")
+                        .color(Color::DARK_GREEN)
+                        .description(format!(
+                            "<@&{}> is the admin role, set by <@{}> on <t:{}:D>",
+                            role.role_id,
+                            role.author_id,
+                            role.timestamp.timestamp()
+                        ))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Admin Role")
+                        .color(Color::RED)
+                        .description(format!("{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Remove the admin role, leaving pool management to server administrators
+#[poise::command(slash_command, guild_only, rename = "clear")]
+pub async fn role_clear(ctx: Context<'_>) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db.delete_admin_role(&guild_id).await {
+        Ok(_) => {
+            info!("Removed admin role on guild {}", guild_id);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Admin Role")
+                        .color(Color::DARK_GREEN)
+                        .description("The admin role was removed")
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to remove admin role on {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Admin Role")
+                        .color(Color::RED)
+                        .description(format!("Failed to remove the admin role:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use super::super::db::mongo::DB;
+use crate::Context;
+use futures::{Stream, StreamExt};
+
+/// Suggests the pools this guild can see
+pub async fn autocomplete_pools<'a>(
+    ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Stream<Item = String> + 'a {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().map(|guild_id| guild_id.to_string());
+    let pools = db
+        .list_visible_pools(guild_id.as_deref())
+        .await
+        .unwrap_or(vec![]);
+
+    futures::stream::iter(pools)
+        .filter(move |pool| futures::future::ready(pool.pool_name.starts_with(partial)))
+        .map(|pool| pool.pool_name)
+}
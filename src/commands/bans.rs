@@ -1,81 +1,557 @@
-use super::super::db::mongo::DB;
+use super::super::db::mongo::{ActionResult, Ban, BanTarget, JobKind, DB};
+use super::autocomplete::autocomplete_pools;
+use super::checks::{author_editor, is_admin};
+use super::confirm::{confirm_import, finish_import};
+use crate::enforcement::{add_pool_bans, list_users, queue};
 use crate::{Context, Error};
-use futures::{Stream, StreamExt};
+use chrono::{Duration, Utc};
 use log::{error, info};
+use poise::serenity_prelude as serenity;
 use serenity::utils::Color;
+use std::collections::HashSet;
 
-async fn autocomplete_pools<'a>(
-    _: Context<'_>,
-    partial: &'a str,
-) -> impl Stream<Item = String> + 'a {
-    let db = DB::init().await.unwrap();
-    let pools = db.list_pools().await.unwrap_or(vec![]);
+// Larger files are refused before they are downloaded
+const MAX_IMPORT_BYTES: u64 = 1024 * 1024;
+// How many invalid rows are shown in the import preview
+const MAX_INVALID_SHOWN: usize = 10;
+// Longer values from user input are cut short when echoed back
+const MAX_SHOWN_VALUE_CHARS: usize = 40;
+// Embed field values may not be longer than 1024 characters
+const MAX_FIELD_CHARS: usize = 1000;
+// How many users `/ban add` takes at once, larger lists go through an import
+const MAX_USERS_PER_ADD: usize = 25;
 
-    futures::stream::iter(pools)
-        .filter(move |pool| futures::future::ready(pool.pool_name.starts_with(partial)))
+/// Names of the pools this guild may see, private pools it isn't a member of
+/// are left out
+async fn visible_pool_names(ctx: Context<'_>, db: &DB) -> anyhow::Result<HashSet<String>> {
+    let guild_id = ctx.guild_id().map(|guild_id| guild_id.to_string());
+    Ok(db
+        .list_visible_pools(guild_id.as_deref())
+        .await?
+        .into_iter()
         .map(|pool| pool.pool_name)
+        .collect())
+}
+
+/// Describes how many subscribed guilds a ban or unban was queued for
+fn describe_queued(servers: &[String], action: &str) -> String {
+    if servers.is_empty() {
+        String::from("No guilds are subscribed to this pool.")
+    } else {
+        format!(
+            "{action} was queued for {} subscribed guilds. Use `/ban actions` to follow its progress.",
+            servers.len()
+        )
+    }
+}
+
+/// Cuts user input short enough to echo back in an embed
+fn shorten(value: &str) -> String {
+    match value.char_indices().nth(MAX_SHOWN_VALUE_CHARS) {
+        Some((end, _)) => format!("{}...", &value[..end]),
+        None => value.to_string(),
+    }
+}
+
+/// Lists invalid import rows one per line, cutting the list short after
+/// `MAX_INVALID_SHOWN` rows or once it would no longer fit in an embed field
+fn describe_invalid(invalid: &[String]) -> String {
+    let mut report = String::new();
+    for (shown, line) in invalid.iter().enumerate() {
+        if shown == MAX_INVALID_SHOWN || report.len() + line.len() + 1 > MAX_FIELD_CHARS {
+            report.push_str(&format!("...and {} more", invalid.len() - shown));
+            break;
+        }
+        report.push_str(line);
+        report.push('\n');
+    }
+    report
+}
+
+/// Reads a user ID from a raw snowflake or a mention
+fn parse_user_id(input: &str) -> Result<serenity::UserId, String> {
+    let id = input
+        .trim()
+        .trim_start_matches("<@")
+        .trim_start_matches('!')
+        .trim_end_matches('>');
+    // snowflakes are currently 17 to 20 digits long
+    if !(17..=20).contains(&id.len()) || !id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!(
+            "`{}` is not a Discord user ID or mention. Copy the ID from the user's profile with Developer Mode enabled.",
+            shorten(input)
+        ));
+    }
+    id.parse::<u64>()
+        .map(serenity::UserId)
+        .map_err(|_| format!("`{}` is not a valid Discord user ID", shorten(input)))
+}
+
+/// Reads user IDs or mentions separated by spaces or commas, failing with
+/// every entry that isn't one
+fn parse_user_ids(input: &str) -> Result<Vec<serenity::UserId>, String> {
+    let entries: Vec<&str> = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+        .collect();
+    if entries.is_empty() {
+        return Err(String::from("Enter at least one user ID or mention"));
+    }
+    if entries.len() > MAX_USERS_PER_ADD {
+        return Err(format!(
+            "At most {MAX_USERS_PER_ADD} users can be added at once, use `/ban import` for more"
+        ));
+    }
+
+    let mut user_ids = Vec::new();
+    let mut invalid = Vec::new();
+    for entry in entries {
+        match parse_user_id(entry) {
+            Ok(user_id) if !user_ids.contains(&user_id) => user_ids.push(user_id),
+            Ok(_) => {}
+            Err(e) => invalid.push(e),
+        }
+    }
+    if invalid.is_empty() {
+        Ok(user_ids)
+    } else {
+        Err(invalid.join("\n"))
+    }
+}
+
+/// Works out who a ban targets from either the user picker or typed IDs,
+/// making sure every user exists
+async fn resolve_users(
+    ctx: Context<'_>,
+    user: Option<serenity::User>,
+    user_ids: Option<String>,
+) -> Result<Vec<serenity::User>, String> {
+    let user_ids = match (user, user_ids) {
+        (Some(user), None) => return Ok(vec![user]),
+        (None, Some(user_ids)) => parse_user_ids(&user_ids)?,
+        _ => return Err(String::from("Pick a user or enter user IDs, but not both")),
+    };
+    let mut users = Vec::with_capacity(user_ids.len());
+    for user_id in user_ids {
+        let user = user_id
+            .to_user(ctx)
+            .await
+            .map_err(|e| format!("Unable to find a Discord user with the ID `{user_id}`:\n{e}"))?;
+        users.push(user);
+    }
+    Ok(users)
+}
+
+/// A row of an import file, numbered from 1 by line for CSV and by array
+/// element for JSON
+struct ImportRow {
+    row: usize,
+    user_id: String,
+    reason: Option<String>,
+}
+
+/// Reads `user_id[,reason]` lines, skipping blank lines and a header
+fn parse_csv(content: &str) -> Vec<ImportRow> {
+    let mut rows = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (user_id, reason) = match line.split_once(',') {
+            Some((user_id, reason)) => (user_id, Some(reason)),
+            None => (line, None),
+        };
+        let user_id = user_id.trim().trim_matches('"');
+        if index == 0 && user_id.eq_ignore_ascii_case("user_id") {
+            continue;
+        }
+        rows.push(ImportRow {
+            row: index + 1,
+            user_id: user_id.to_string(),
+            reason: reason
+                .map(|reason| reason.trim().trim_matches('"').to_string())
+                .filter(|reason| !reason.is_empty()),
+        });
+    }
+    rows
+}
+
+/// Reads an array of user IDs, or of objects with a `user_id` and an optional
+/// `reason`
+fn parse_json(content: &str) -> Result<Vec<ImportRow>, String> {
+    let entries: Vec<serde_json::Value> = serde_json::from_str(content)
+        .map_err(|e| format!("The file is not a JSON array of bans:\n{e}"))?;
+    let rows = entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let (user_id, reason) = match entry {
+                serde_json::Value::Object(mut object) => {
+                    (object.remove("user_id"), object.remove("reason"))
+                }
+                value => (Some(value), None),
+            };
+            let user_id = match user_id {
+                Some(serde_json::Value::String(user_id)) => user_id,
+                Some(serde_json::Value::Number(user_id)) => user_id.to_string(),
+                _ => String::new(),
+            };
+            ImportRow {
+                row: index + 1,
+                user_id,
+                reason: reason
+                    .as_ref()
+                    .and_then(serde_json::Value::as_str)
+                    .map(str::trim)
+                    .filter(|reason| !reason.is_empty())
+                    .map(String::from),
+            }
+        })
+        .collect();
+    Ok(rows)
 }
 
-#[poise::command(slash_command, subcommands("add", "list", "remove"))]
+#[poise::command(
+    slash_command,
+    subcommands("actions", "add", "import", "list", "remove")
+)]
 pub async fn ban(_: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-/// Add a new ban to a pool
-#[poise::command(slash_command)]
+/// Add new bans to a pool for one or more users
+#[poise::command(slash_command, check = "is_admin")]
 pub async fn add(
     ctx: Context<'_>,
-    #[description = "Target User ID"] user_id: String,
     #[description = "Banpool Name"]
     #[autocomplete = "autocomplete_pools"]
     pool: String,
     #[description = "Ban Reason"] reason: String,
+    #[description = "Target User"] user: Option<serenity::User>,
+    #[description = "Target user IDs or mentions separated by spaces or commas, for users that can't be picked"]
+    user_id: Option<String>,
+    #[description = "Remove the ban from the pool after this many days"]
+    #[min = 1]
+    #[max = 36500]
+    days: Option<i64>,
+    #[description = "Days of messages subscribed guilds purge, overriding their setting"]
+    #[max = 7]
+    delete_message_days: Option<u8>,
 ) -> Result<(), Error> {
     let db = DB::init().await.unwrap();
-    let author_id = ctx.author().id;
-    match db
-        .add_ban(&user_id, &pool, &reason, &author_id.to_string())
-        .await
-    {
-        Ok(_) => {
-            info!("Added pool: {}", user_id);
+    let editor = author_editor(ctx).await?;
+    let users = match resolve_users(ctx, user, user_id).await {
+        Ok(users) => users,
+        Err(e) => {
+            ctx.send(|r| r.embed(|r| r.title("Create Ban").color(Color::RED).description(&e)))
+                .await?;
+            return Err(e.into());
+        }
+    };
+    let expires_at = match days {
+        Some(days) => match Duration::try_days(days)
+            .and_then(|duration| Utc::now().checked_add_signed(duration))
+        {
+            Some(expires_at) => Some(expires_at),
+            None => {
+                let e = format!("A ban can't expire {days} days from now");
+                ctx.send(|r| r.embed(|r| r.title("Create Ban").color(Color::RED).description(&e)))
+                    .await?;
+                return Err(e.into());
+            }
+        },
+        None => None,
+    };
+    let expiry = match expires_at {
+        Some(expires_at) => format!(" until <t:{}:f>", expires_at.timestamp()),
+        None => String::new(),
+    };
+
+    let mut lines = Vec::with_capacity(users.len());
+    let mut queued = None;
+    let mut failure = None;
+    let mut added = 0;
+    for user in &users {
+        let user_id = user.id.to_string();
+        let target = BanTarget {
+            user_id: user_id.clone(),
+            username: Some(user.name.clone()),
+            discriminator: Some(user.discriminator),
+            avatar_url: user.avatar_url(),
+        };
+        if let Err(e) = db
+            .add_ban(
+                &target,
+                &pool,
+                &reason,
+                &editor,
+                expires_at,
+                delete_message_days,
+            )
+            .await
+        {
+            error!("Failed to add {} to {} banpool", user_id, pool);
+            lines.push(format!("Failed to add {user_id} to {pool}:\n{e}"));
+            failure = Some(e);
+            continue;
+        }
+
+        info!("Added pool: {}", user_id);
+        added += 1;
+        lines.push(format!(
+            "{} (`{user_id}`) was added to {pool}{expiry} successfully",
+            user.tag()
+        ));
+        match queue::enqueue_pool(&db, JobKind::Enforce, &user_id, &pool, &reason).await {
+            Ok(servers) => queued = Some(describe_queued(&servers, "Enforcement")),
+            Err(e) => {
+                error!("Failed to queue ban of {} from {}: {}", user_id, pool, e);
+                lines.push(format!(
+                    "Failed to queue enforcement of {user_id} in subscribed guilds:\n{e}"
+                ));
+            }
+        }
+    }
+
+    // every subscribed guild gets the same jobs, so one summary covers all users
+    let report = queued
+        .map(|queued| format!("\n\n{queued}"))
+        .unwrap_or_default();
+    let description = format!("{}{report}", lines.join("\n"));
+    // the command only failed if not a single user could be added
+    match failure {
+        Some(e) if added == 0 => {
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Create Ban")
+                        .color(Color::RED)
+                        .description(description)
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+        _ => {
             ctx.send(|r| {
                 r.embed(|r| {
                     r.title("Create Ban")
                         .color(Color::DARK_GREEN)
-                        .description(format!("{user_id} was added to {pool} successfully"))
+                        .description(description)
                 })
             })
             .await?;
             Ok(())
         }
+    }
+}
+
+/// Add many bans to a pool from a CSV or JSON file of user IDs and reasons
+#[poise::command(slash_command, check = "is_admin")]
+pub async fn import(
+    ctx: Context<'_>,
+    #[description = "Banpool Name"]
+    #[autocomplete = "autocomplete_pools"]
+    pool: String,
+    #[description = "CSV lines of user_id,reason or a JSON array of {user_id, reason}"]
+    file: serenity::Attachment,
+    #[description = "Reason for rows that don't give one"] reason: Option<String>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let db = DB::init().await.unwrap();
+    let editor = author_editor(ctx).await?;
+
+    // the preview reveals who is in the pool, so only its editors may see it
+    if let Err(e) = db.check_pool_access(&pool, &editor, false).await {
+        ctx.send(|r| {
+            r.embed(|r| {
+                r.title("Import Bans")
+                    .color(Color::RED)
+                    .description(format!("Unable to import into `{pool}`:\n{e}"))
+            })
+        })
+        .await?;
+        return Err(e.into());
+    }
+
+    let rows = match read_import_file(&file).await {
+        Ok(rows) => rows,
         Err(e) => {
-            error!("Failed to add {} to {} banpool", user_id, pool);
+            ctx.send(|r| r.embed(|r| r.title("Import Bans").color(Color::RED).description(&e)))
+                .await?;
+            return Err(e.into());
+        }
+    };
+
+    let mut seen: HashSet<String> = match db.get_pool_bans(&pool).await {
+        Ok(bans) => bans.into_iter().map(|ban| ban.user_id).collect(),
+        Err(e) => {
+            error!("Failed to list bans of {}: {}", pool, e);
             ctx.send(|r| {
                 r.embed(|r| {
-                    r.title("Create Ban")
+                    r.title("Import Bans")
                         .color(Color::RED)
-                        .description(format!("Failed to add {user_id} to {pool}:\n{e}"))
+                        .description(format!("Unable to read the bans of `{pool}`:\n{e}"))
                 })
             })
             .await?;
+            return Err(e.into());
+        }
+    };
+    let default_reason = reason.unwrap_or_else(|| String::from("No reason given"));
+    let now = Utc::now();
+    let mut bans = Vec::new();
+    let mut duplicates = 0;
+    let mut invalid = Vec::new();
+    for row in rows {
+        let user_id = match parse_user_id(&row.user_id) {
+            Ok(user_id) => user_id.to_string(),
+            Err(_) => {
+                invalid.push(format!("Row {}: `{}`", row.row, shorten(&row.user_id)));
+                continue;
+            }
+        };
+        // already in the pool or repeated in the file
+        if !seen.insert(user_id.clone()) {
+            duplicates += 1;
+            continue;
+        }
+        bans.push(Ban {
+            user_id,
+            pool_name: pool.clone(),
+            reason: row.reason.unwrap_or_else(|| default_reason.clone()),
+            creator_id: editor.user_id.clone(),
+            expires_at: None,
+            delete_message_days: None,
+            username: None,
+            discriminator: None,
+            avatar_url: None,
+            timestamp: now,
+        });
+    }
+
+    let counts = format!(
+        "{} new, {} already in `{pool}` or repeated, {} invalid.",
+        bans.len(),
+        duplicates,
+        invalid.len()
+    );
+    let invalid_report = describe_invalid(&invalid);
+
+    if bans.is_empty() {
+        ctx.send(|r| {
+            r.embed(|r| {
+                r.title("Import Bans")
+                    .color(Color::DARK_GREEN)
+                    .description(format!("Nothing to import. {counts}"));
+                if !invalid.is_empty() {
+                    r.field("Invalid", &invalid_report, false);
+                }
+                r
+            })
+        })
+        .await?;
+        return Ok(());
+    }
+
+    let new_users: Vec<String> = bans.iter().map(|ban| ban.user_id.clone()).collect();
+    let reply = match confirm_import(ctx, "Import Bans", |r| {
+        r.description(counts.as_str())
+            .field("Users", list_users(&new_users, 1000), false);
+        if !invalid.is_empty() {
+            r.field("Invalid", &invalid_report, false);
+        }
+        r
+    })
+    .await?
+    {
+        Some(reply) => reply,
+        None => return Ok(()),
+    };
+
+    match add_pool_bans(&db, &pool, &editor, bans, None).await {
+        Ok(added) => {
+            info!(
+                "Imported {} bans from {} into {}",
+                added.len(),
+                file.filename,
+                pool
+            );
+            let description = format!(
+                "{} bans were imported into `{pool}`. Use `/ban actions` to follow their enforcement.",
+                added.len()
+            );
+            finish_import(ctx, &reply, "Import Bans", Color::DARK_GREEN, &description).await
+        }
+        Err(e) => {
+            error!("Failed to import {} into {}: {}", file.filename, pool, e);
+            let description = format!("Failed to import bans into `{pool}`:\n{e}");
+            finish_import(ctx, &reply, "Import Bans", Color::RED, &description).await?;
             Err(e.into())
         }
     }
 }
 
+/// Downloads an import file and reads its rows, as JSON when it is named
+/// `.json` or starts with `[` and as CSV otherwise
+async fn read_import_file(file: &serenity::Attachment) -> Result<Vec<ImportRow>, String> {
+    if file.size > MAX_IMPORT_BYTES {
+        return Err(format!(
+            "`{}` is too large, import files can be at most 1 MB",
+            file.filename
+        ));
+    }
+    let bytes = file
+        .download()
+        .await
+        .map_err(|e| format!("Unable to download `{}`:\n{e}", file.filename))?;
+    let content = String::from_utf8(bytes)
+        .map_err(|_| format!("`{}` is not a UTF-8 text file", file.filename))?;
+
+    let rows = if file.filename.to_lowercase().ends_with(".json")
+        || content.trim_start().starts_with('[')
+    {
+        parse_json(&content)?
+    } else {
+        parse_csv(&content)
+    };
+    if rows.is_empty() {
+        return Err(format!("`{}` does not contain any bans", file.filename));
+    }
+    Ok(rows)
+}
+
 #[poise::command(slash_command)]
 pub async fn list(
     ctx: Context<'_>,
     #[description = "Target User ID"] user_id: String,
 ) -> Result<(), Error> {
     let db = DB::init().await.unwrap();
-    match db.get_user_bans(&user_id).await {
+    let bans = match visible_pool_names(ctx, &db).await {
+        Ok(visible) => db.get_user_bans(&user_id).await.map(|bans| {
+            bans.into_iter()
+                .filter(|ban| visible.contains(&ban.pool_name))
+                .collect::<Vec<_>>()
+        }),
+        Err(e) => Err(e),
+    };
+    match bans {
         Ok(bans) => {
             let mut ban_string = String::new();
+            // the profile recorded with the ban, the user may have renamed since
+            if let Some(username) = bans.iter().find_map(|ban| ban.username.as_ref()) {
+                ban_string.push_str(&format!("Banned as {username}\n"));
+            }
 
             for ban in bans {
-                ban_string.push_str(format!("{}\n", ban.pool_name).as_str());
+                match ban.expires_at {
+                    Some(expires_at) => ban_string.push_str(&format!(
+                        "{} (expires <t:{}:R>)\n",
+                        ban.pool_name,
+                        expires_at.timestamp()
+                    )),
+                    None => ban_string.push_str(format!("{}\n", ban.pool_name).as_str()),
+                }
             }
 
             ctx.send(|r| {
@@ -104,7 +580,7 @@ pub async fn list(
 }
 
 /// Remove a target ban by User ID and Pool name
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "is_admin")]
 pub async fn remove(
     ctx: Context<'_>,
     #[description = "Target User ID"] user_id: String,
@@ -113,14 +589,24 @@ pub async fn remove(
     pool: String,
 ) -> Result<(), Error> {
     let db = DB::init().await.unwrap();
-    match db.delete_ban(&user_id, &pool).await {
+    let editor = author_editor(ctx).await?;
+    match db.delete_ban(&user_id, &pool, &editor).await {
         Ok(_) => {
             info!("Removed {} from {} pool", user_id, &pool);
+            let report = match queue::enqueue_pool(&db, JobKind::Lift, &user_id, &pool, "").await {
+                Ok(servers) => describe_queued(&servers, "Lifting the ban"),
+                Err(e) => {
+                    error!("Failed to queue unban of {} from {}: {}", user_id, pool, e);
+                    format!("Failed to queue unbans in subscribed guilds:\n{e}")
+                }
+            };
             ctx.send(|r| {
                 r.embed(|r| {
                     r.title("Remove Ban")
                         .color(Color::DARK_GREEN)
-                        .description(format!("{user_id} was removed from {pool} successfully"))
+                        .description(format!(
+                            "{user_id} was removed from {pool} successfully\n\n{report}"
+                        ))
                 })
             })
             .await?;
@@ -140,3 +626,197 @@ pub async fn remove(
         }
     }
 }
+
+/// Show where the bot has acted on a user's pool bans
+#[poise::command(slash_command)]
+pub async fn actions(
+    ctx: Context<'_>,
+    #[description = "Target User ID"] user_id: String,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().map(|guild_id| guild_id.to_string());
+    // other guilds' actions are only shown for pools this guild can see
+    let actions = match visible_pool_names(ctx, &db).await {
+        Ok(visible) => db.get_user_actions(&user_id).await.map(|actions| {
+            actions
+                .into_iter()
+                .filter(|action| {
+                    visible.contains(&action.pool_name)
+                        || guild_id.as_deref() == Some(action.server_id.as_str())
+                })
+                .collect::<Vec<_>>()
+        }),
+        Err(e) => Err(e),
+    };
+    match actions {
+        Ok(actions) => {
+            let mut action_string = String::new();
+
+            for action in &actions {
+                let result = match action.result {
+                    ActionResult::Success => String::from("success"),
+                    ActionResult::Failure => {
+                        format!("failed: {}", action.error.as_deref().unwrap_or("unknown"))
+                    }
+                };
+                let expiry = match action.expires_at {
+                    Some(expires_at) => format!(" until <t:{}:f>", expires_at.timestamp()),
+                    None => String::new(),
+                };
+                let line = format!(
+                    "<t:{}:f> {:?}{expiry} in `{}` via {} ({result})\n",
+                    action.timestamp.timestamp(),
+                    action.action,
+                    action.server_id,
+                    action.pool_name
+                );
+                if action_string.len() + line.len() > 4000 {
+                    action_string.push_str("...");
+                    break;
+                }
+                action_string.push_str(&line);
+            }
+            if actions.is_empty() {
+                action_string.push_str("No actions have been taken against this user.");
+            }
+
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Ban Actions")
+                        .color(Color::DARK_GREEN)
+                        .description(action_string)
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Unable to fetch actions for user ID {}: {}", user_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Ban Actions")
+                        .color(Color::RED)
+                        .description(format!(
+                            "Unable to fetch actions for User ID {user_id}\n{e}"
+                        ))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_skips_header_and_blank_lines() {
+        let rows = parse_csv("user_id,reason\n\n123456789012345678,spam\n");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].row, 3);
+        assert_eq!(rows[0].user_id, "123456789012345678");
+        assert_eq!(rows[0].reason.as_deref(), Some("spam"));
+    }
+
+    #[test]
+    fn csv_unquotes_ids_and_reasons() {
+        let rows =
+            parse_csv("\"123456789012345678\", \"scam, phishing links\"\n234567890123456789");
+        assert_eq!(rows[0].user_id, "123456789012345678");
+        assert_eq!(rows[0].reason.as_deref(), Some("scam, phishing links"));
+        assert_eq!(rows[1].reason, None);
+    }
+
+    #[test]
+    fn json_reads_strings_numbers_and_objects() {
+        let rows = parse_json(
+            r#"["123456789012345678", 234567890123456789, {"user_id": "345678901234567890", "reason": " raid "}]"#,
+        )
+        .unwrap();
+        let ids: Vec<&str> = rows.iter().map(|row| row.user_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "123456789012345678",
+                "234567890123456789",
+                "345678901234567890"
+            ]
+        );
+        assert_eq!(rows[2].reason.as_deref(), Some("raid"));
+    }
+
+    #[test]
+    fn json_keeps_bad_rows_for_validation() {
+        let rows = parse_json(r#"[true, {"reason": "no id"}]"#).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|row| parse_user_id(&row.user_id).is_err()));
+    }
+
+    #[test]
+    fn json_rejects_non_arrays() {
+        assert!(parse_json(r#"{"user_id": "123456789012345678"}"#).is_err());
+    }
+
+    #[test]
+    fn user_id_accepts_mentions() {
+        let expected = serenity::UserId(123456789012345678);
+        assert_eq!(parse_user_id("123456789012345678"), Ok(expected));
+        assert_eq!(parse_user_id("<@123456789012345678>"), Ok(expected));
+        assert_eq!(parse_user_id(" <@!123456789012345678> "), Ok(expected));
+    }
+
+    #[test]
+    fn user_id_rejects_bad_input() {
+        assert!(parse_user_id("").is_err());
+        assert!(parse_user_id("1234").is_err());
+        assert!(parse_user_id("12345678901234567a").is_err());
+        assert!(parse_user_id("99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn user_ids_split_on_spaces_and_commas() {
+        let ids = parse_user_ids("123456789012345678, <@234567890123456789>\n123456789012345678")
+            .unwrap();
+        assert_eq!(
+            ids,
+            [
+                serenity::UserId(123456789012345678),
+                serenity::UserId(234567890123456789)
+            ]
+        );
+    }
+
+    #[test]
+    fn user_ids_report_every_bad_entry() {
+        let e = parse_user_ids("123456789012345678 foo,1234").unwrap_err();
+        assert!(e.contains("`foo`") && e.contains("`1234`"));
+        assert!(parse_user_ids(" , ").is_err());
+    }
+
+    #[test]
+    fn shorten_cuts_long_values() {
+        assert_eq!(shorten("123"), "123");
+        let long = "é".repeat(100);
+        assert_eq!(
+            shorten(&long),
+            format!("{}...", "é".repeat(MAX_SHOWN_VALUE_CHARS))
+        );
+    }
+
+    #[test]
+    fn invalid_report_fits_in_a_field() {
+        let invalid: Vec<String> = (1..=50)
+            .map(|row| format!("Row {row}: `{}`", shorten(&"x".repeat(500))))
+            .collect();
+        let report = describe_invalid(&invalid);
+        assert!(report.len() < 1024);
+        assert!(report.ends_with("...and 40 more"));
+
+        let wide: Vec<String> = (1..=5)
+            .map(|row| format!("{row}{}", "x".repeat(300)))
+            .collect();
+        assert!(describe_invalid(&wide).len() < 1024);
+    }
+}
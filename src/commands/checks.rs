@@ -0,0 +1,92 @@
+use super::super::db::mongo::{PoolEditor, DB};
+use crate::{Context, Error};
+use log::info;
+use poise::serenity_prelude as serenity;
+use serenity::utils::Color;
+
+/// Whether the member may manage pools from this guild: guild administrators
+/// always can, everyone else needs the guild's admin role
+pub async fn has_admin_access(db: &DB, member: &serenity::Member) -> bool {
+    if member
+        .permissions
+        .is_some_and(|permissions| permissions.administrator())
+    {
+        return true;
+    }
+
+    match db.list_admin_role(&member.guild_id.to_string()).await {
+        Ok(admin_role) => member
+            .roles
+            .iter()
+            .any(|role| role.to_string() == admin_role.role_id),
+        Err(_) => false,
+    }
+}
+
+/// Describes the member as an editor of pools
+pub fn pool_editor(member: &serenity::Member) -> PoolEditor {
+    PoolEditor {
+        server_id: member.guild_id.to_string(),
+        user_id: member.user.id.to_string(),
+        role_ids: member.roles.iter().map(|role| role.to_string()).collect(),
+    }
+}
+
+/// The command's author as an editor of pools
+pub async fn author_editor(ctx: Context<'_>) -> Result<PoolEditor, Error> {
+    match ctx.author_member().await {
+        Some(member) => Ok(pool_editor(&member)),
+        None => Err("Pools can only be changed from a server".into()),
+    }
+}
+
+async fn deny(ctx: Context<'_>, reason: &str) -> Result<bool, Error> {
+    info!(
+        "Denied {} to {}",
+        ctx.command().qualified_name,
+        ctx.author().id
+    );
+    ctx.send(|r| {
+        r.embed(|r| {
+            r.title("Permission Denied")
+                .color(Color::RED)
+                .description(reason)
+        })
+        .ephemeral(true)
+    })
+    .await?;
+    Ok(false)
+}
+
+/// Command check for management commands
+pub async fn is_admin(ctx: Context<'_>) -> Result<bool, Error> {
+    let member = match ctx.author_member().await {
+        Some(member) => member,
+        None => return deny(ctx, "This command can only be used in a server").await,
+    };
+
+    let db = DB::init().await.unwrap();
+    if has_admin_access(&db, &member).await {
+        Ok(true)
+    } else {
+        deny(
+            ctx,
+            "You need this server's admin role to use this command. Server administrators can set it with `/admin role set`.",
+        )
+        .await
+    }
+}
+
+/// Command check for commands only guild administrators may use
+pub async fn is_guild_admin(ctx: Context<'_>) -> Result<bool, Error> {
+    let is_admin = ctx
+        .author_member()
+        .await
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.administrator());
+    if is_admin {
+        Ok(true)
+    } else {
+        deny(ctx, "Only server administrators can use this command").await
+    }
+}
@@ -0,0 +1,398 @@
+use super::super::db::mongo::{SubscriptionLevel, DB};
+use super::checks::is_admin;
+use crate::{Context, Error};
+use log::{error, info};
+use poise::serenity_prelude as serenity;
+use serenity::utils::Color;
+
+#[poise::command(
+    slash_command,
+    check = "is_admin",
+    subcommands(
+        "account_age",
+        "delete_messages",
+        "quarantine",
+        "reconcile",
+        "shadow",
+        "show",
+        "timeout"
+    )
+)]
+pub async fn config(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Only report what pool enforcement would do in this guild instead of acting
+#[poise::command(slash_command, guild_only)]
+pub async fn shadow(
+    ctx: Context<'_>,
+    #[description = "Enable shadow mode"] enabled: bool,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db
+        .set_shadow_mode(&guild_id, enabled, &ctx.author().id.to_string())
+        .await
+    {
+        Ok(_) => {
+            info!("Set shadow mode to {} on guild {}", enabled, guild_id);
+            let state = if enabled { "enabled" } else { "disabled" };
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Shadow Mode")
+                        .color(Color::DARK_GREEN)
+                        .description(format!("Shadow mode was {state} for this server"))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to set shadow mode on {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Shadow Mode")
+                        .color(Color::RED)
+                        .description(format!("Failed to set shadow mode:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Choose whether drift between pools and this guild's bans is repaired or reported
+#[poise::command(slash_command, guild_only)]
+pub async fn reconcile(
+    ctx: Context<'_>,
+    #[description = "Repair drift instead of reporting it"] repair: bool,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db
+        .set_reconcile_repair(&guild_id, repair, &ctx.author().id.to_string())
+        .await
+    {
+        Ok(_) => {
+            info!("Set reconcile repair to {} on guild {}", repair, guild_id);
+            let mode = if repair {
+                "repaired, or only reported while shadow mode is on"
+            } else {
+                "reported"
+            };
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Reconciliation")
+                        .color(Color::DARK_GREEN)
+                        .description(format!("Drift from subscribed pools will be {mode}"))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to set reconcile repair on {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Reconciliation")
+                        .color(Color::RED)
+                        .description(format!("Failed to set the reconciliation mode:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Handle accounts younger than a minimum age when they join
+#[poise::command(slash_command, guild_only, rename = "account-age")]
+pub async fn account_age(
+    ctx: Context<'_>,
+    #[description = "Minimum account age in days, 0 to disable"]
+    #[min = 0]
+    #[max = 365]
+    days: i64,
+    #[description = "What to do with newer accounts"] action: Option<SubscriptionLevel>,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    let action = action.unwrap_or(SubscriptionLevel::NotifyOnly);
+    let min_days = if days > 0 { Some(days) } else { None };
+    match db
+        .set_account_age(&guild_id, min_days, action, &ctx.author().id.to_string())
+        .await
+    {
+        Ok(_) => {
+            info!(
+                "Set minimum account age to {} days on guild {}",
+                days, guild_id
+            );
+            let description = match min_days {
+                Some(days) => format!("Accounts younger than {days} days will get {action:?}"),
+                None => String::from("The account age gate was disabled"),
+            };
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Account Age")
+                        .color(Color::DARK_GREEN)
+                        .description(description)
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to set minimum account age on {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Account Age")
+                        .color(Color::RED)
+                        .description(format!("Failed to set the minimum account age:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Set how many days of messages are purged when banning pool members
+#[poise::command(slash_command, guild_only, rename = "delete-messages")]
+pub async fn delete_messages(
+    ctx: Context<'_>,
+    #[description = "Days of messages to delete"]
+    #[max = 7]
+    days: u8,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db
+        .set_delete_message_days(&guild_id, days, &ctx.author().id.to_string())
+        .await
+    {
+        Ok(_) => {
+            info!(
+                "Set message deletion to {} days on guild {}",
+                days, guild_id
+            );
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Message Deletion")
+                        .color(Color::DARK_GREEN)
+                        .description(format!(
+                            "Pool bans will delete the last {days} days of messages"
+                        ))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to set message deletion on {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Message Deletion")
+                        .color(Color::RED)
+                        .description(format!("Failed to set message deletion:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Show this guild's configuration
+#[poise::command(slash_command, guild_only)]
+pub async fn show(ctx: Context<'_>) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db.get_server_config(&guild_id).await {
+        Ok(config) => {
+            let shadow_mode = config.as_ref().is_some_and(|config| config.shadow_mode);
+            let reconcile_repair = config
+                .as_ref()
+                .is_some_and(|config| config.reconcile_repair);
+            let timeout = match config.as_ref().and_then(|config| config.timeout_minutes) {
+                Some(minutes) => format!("{minutes} minutes"),
+                None => String::from("Default (1 day)"),
+            };
+            let account_age = match config.as_ref().and_then(|config| {
+                config
+                    .min_account_age_days
+                    .map(|days| (days, config.account_age_action))
+            }) {
+                Some((days, action)) => format!(
+                    "{days} days, then {:?}",
+                    action.unwrap_or(SubscriptionLevel::NotifyOnly)
+                ),
+                None => String::from("Disabled"),
+            };
+            let delete_messages = config
+                .as_ref()
+                .and_then(|config| config.delete_message_days)
+                .unwrap_or(0);
+            let quarantine_role = match db.list_quarantine_role(&guild_id).await {
+                Ok(role) => format!("<@&{}>", role.role_id),
+                Err(_) => String::from("Not set"),
+            };
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Server Config")
+                        .color(Color::DARK_GREEN)
+                        .field("Shadow mode", shadow_mode, false)
+                        .field("Quarantine role", quarantine_role, false)
+                        .field("Timeout duration", timeout, false)
+                        .field("Repair drift", reconcile_repair, false)
+                        .field("Minimum account age", account_age, false)
+                        .field(
+                            "Messages purged on ban",
+                            format!("{delete_messages} days"),
+                            false,
+                        )
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to fetch config for {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Server Config")
+                        .color(Color::RED)
+                        .description(format!("Failed to fetch server config:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Set how long timeout subscriptions mute pool members for
+#[poise::command(slash_command, guild_only)]
+pub async fn timeout(
+    ctx: Context<'_>,
+    #[description = "Timeout duration in minutes"]
+    #[min = 1]
+    #[max = 40320]
+    minutes: i64,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db
+        .set_timeout_minutes(&guild_id, minutes, &ctx.author().id.to_string())
+        .await
+    {
+        Ok(_) => {
+            info!("Set timeout to {} minutes on guild {}", minutes, guild_id);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Timeout Duration")
+                        .color(Color::DARK_GREEN)
+                        .description(format!(
+                            "Pool members will be timed out for {minutes} minutes"
+                        ))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to set timeout duration on {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Timeout Duration")
+                        .color(Color::RED)
+                        .description(format!("Failed to set the timeout duration:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+#[poise::command(slash_command, subcommands("quarantine_set", "quarantine_clear"))]
+pub async fn quarantine(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set the role given to pool members by quarantine subscriptions
+#[poise::command(slash_command, guild_only, rename = "set")]
+pub async fn quarantine_set(
+    ctx: Context<'_>,
+    #[description = "Quarantine Role"] role: serenity::Role,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db
+        .add_quarantine_role(
+            &guild_id,
+            &role.id.to_string(),
+            &ctx.author().id.to_string(),
+        )
+        .await
+    {
+        Ok(_) => {
+            info!("Set {} as quarantine role on guild {}", role.id, guild_id);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Quarantine Role")
+                        .color(Color::DARK_GREEN)
+                        .description(format!("<@&{}> is now the quarantine role", role.id))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to set quarantine role on {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Quarantine Role")
+                        .color(Color::RED)
+                        .description(format!("Failed to set the quarantine role:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Remove the quarantine role
+#[poise::command(slash_command, guild_only, rename = "clear")]
+pub async fn quarantine_clear(ctx: Context<'_>) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db.delete_quarantine_role(&guild_id).await {
+        Ok(_) => {
+            info!("Removed quarantine role on guild {}", guild_id);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Quarantine Role")
+                        .color(Color::DARK_GREEN)
+                        .description("The quarantine role was removed")
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to remove quarantine role on {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Quarantine Role")
+                        .color(Color::RED)
+                        .description(format!("Failed to remove the quarantine role:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
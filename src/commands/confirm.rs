@@ -0,0 +1,82 @@
+use crate::{Context, Error};
+use poise::serenity_prelude as serenity;
+use serenity::utils::Color;
+use std::time::Duration;
+
+// How long an import preview waits for its author to choose
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Previews an import with Import and Cancel buttons and waits for the author
+/// to choose. Returns the reply to report the import's result on once it was
+/// confirmed, or None once it was cancelled or timed out.
+pub async fn confirm_import<'a>(
+    ctx: Context<'a>,
+    title: &str,
+    preview: impl FnOnce(&mut serenity::CreateEmbed) -> &mut serenity::CreateEmbed,
+) -> Result<Option<poise::ReplyHandle<'a>>, Error> {
+    let uuid = ctx.id();
+    let confirm_id = format!("{uuid}-confirm");
+    let cancel_id = format!("{uuid}-cancel");
+    let reply = ctx
+        .send(|r| {
+            r.embed(|r| preview(r.title(title).color(Color::BLUE)))
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_button(|b| {
+                            b.style(serenity::ButtonStyle::Danger)
+                                .label("Import")
+                                .custom_id(&confirm_id)
+                        })
+                        .create_button(|b| {
+                            b.style(serenity::ButtonStyle::Secondary)
+                                .label("Cancel")
+                                .custom_id(&cancel_id)
+                        })
+                    })
+                })
+        })
+        .await?;
+
+    let interaction = serenity::CollectComponentInteraction::new(ctx)
+        .author_id(ctx.author().id)
+        .channel_id(ctx.channel_id())
+        .timeout(CONFIRM_TIMEOUT)
+        .filter(move |mci| mci.data.custom_id.starts_with(&uuid.to_string()))
+        .await;
+    let mci = match interaction {
+        Some(mci) if mci.data.custom_id == confirm_id => mci,
+        _ => {
+            finish_import(
+                ctx,
+                &reply,
+                title,
+                Color::DARK_GREEN,
+                "The import was cancelled",
+            )
+            .await?;
+            return Ok(None);
+        }
+    };
+    mci.create_interaction_response(ctx, |ir| {
+        ir.kind(serenity::InteractionResponseType::DeferredUpdateMessage)
+    })
+    .await?;
+    Ok(Some(reply))
+}
+
+/// Replaces an import preview with how the import went, removing its buttons
+pub async fn finish_import(
+    ctx: Context<'_>,
+    reply: &poise::ReplyHandle<'_>,
+    title: &str,
+    color: Color,
+    description: &str,
+) -> Result<(), Error> {
+    reply
+        .edit(ctx, |r| {
+            r.embed(|r| r.title(title).color(color).description(description))
+                .components(|c| c)
+        })
+        .await?;
+    Ok(())
+}
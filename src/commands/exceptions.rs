@@ -1,4 +1,5 @@
 use super::super::db::mongo::DB;
+use super::checks::is_admin;
 use crate::{Context, Error};
 use log::{error, info};
 use serenity::utils::Color;
@@ -9,7 +10,7 @@ pub async fn exception(_: Context<'_>) -> Result<(), Error> {
 }
 
 /// Create a new exception
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "is_admin")]
 pub async fn add(
     ctx: Context<'_>,
     #[description = "Target User ID"] user_id: String,
@@ -93,7 +94,7 @@ pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
 }
 
 /// Remove an exception by User ID
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "is_admin")]
 pub async fn remove(
     ctx: Context<'_>,
     #[description = "Target User ID"] user_id: String,
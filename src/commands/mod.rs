@@ -1,5 +1,11 @@
+pub mod admin;
+pub mod autocomplete;
 pub mod bans;
+pub mod checks;
+pub mod config;
+pub mod confirm;
 pub mod exceptions;
 pub mod notifications;
 pub mod pools;
+pub mod queue;
 pub mod subscriptions;
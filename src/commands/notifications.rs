@@ -1,22 +1,152 @@
-use super::super::db::mongo;
+use super::super::db::mongo::DB;
+use super::checks::is_admin;
 use crate::{Context, Error};
+use log::{error, info};
+use poise::serenity_prelude as serenity;
+use serenity::utils::Color;
 
-#[poise::command(slash_command, subcommands("add", "remove"))]
+#[poise::command(slash_command, subcommands("add", "list", "remove"))]
 pub async fn notification(_: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-#[poise::command(slash_command)]
-pub async fn add(ctx: Context<'_>) -> Result<(), Error> {
-    Ok(())
+/// Set the channel pool notifications are posted to
+#[poise::command(slash_command, guild_only, rename = "set", check = "is_admin")]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "Notification Channel"]
+    #[channel_types("Text", "News")]
+    channel: serenity::GuildChannel,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+
+    // make sure the bot can actually post there before relying on it
+    if let Err(e) = channel
+        .send_message(ctx, |m| {
+            m.embed(|e| {
+                e.title("Notification Channel")
+                    .color(Color::ORANGE)
+                    .description("Banpool notifications for this server will be posted here")
+            })
+        })
+        .await
+    {
+        error!("Unable to post to {} on {}: {}", channel.id, guild_id, e);
+        ctx.send(|r| {
+            r.embed(|r| {
+                r.title("Notification Channel")
+                    .color(Color::RED)
+                    .description(format!("Unable to post in <#{}>:\n{e}", channel.id))
+            })
+        })
+        .await?;
+        return Err(e.into());
+    }
+
+    match db
+        .add_notification_channel(
+            &guild_id,
+            &channel.id.to_string(),
+            &ctx.author().id.to_string(),
+        )
+        .await
+    {
+        Ok(_) => {
+            info!("Set {} as notification channel on {}", channel.id, guild_id);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Notification Channel")
+                        .color(Color::DARK_GREEN)
+                        .description(format!("Notifications will be posted in <#{}>", channel.id))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to set notification channel on {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Notification Channel")
+                        .color(Color::RED)
+                        .description(format!("Failed to set the notification channel:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
 }
 
-#[poise::command(slash_command)]
+/// Show the channel pool notifications are posted to
+#[poise::command(slash_command, guild_only, rename = "show")]
 pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
-    Ok(())
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db.list_notification_channel(&guild_id).await {
+        Ok(channel) => {
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Notification Channel")
+                        .color(Color::DARK_GREEN)
+                        .description(format!(
+                            "Notifications are posted in <#{}>, set by <@{}> on <t:{}:D>",
+                            channel.channel_id,
+                            channel.author_id,
+                            channel.timestamp.timestamp()
+                        ))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Notification Channel")
+                        .color(Color::RED)
+                        .description(format!("{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
 }
 
-#[poise::command(slash_command)]
+/// Stop posting pool notifications
+#[poise::command(slash_command, guild_only, rename = "clear", check = "is_admin")]
 pub async fn remove(ctx: Context<'_>) -> Result<(), Error> {
-    Ok(())
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db.delete_notification_channel(&guild_id).await {
+        Ok(_) => {
+            info!("Removed notification channel on {}", guild_id);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Notification Channel")
+                        .color(Color::DARK_GREEN)
+                        .description("The notification channel was removed")
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!(
+                "Failed to remove notification channel on {}: {}",
+                guild_id, e
+            );
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Notification Channel")
+                        .color(Color::RED)
+                        .description(format!("Failed to remove the notification channel:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
 }
@@ -1,35 +1,45 @@
-use super::super::db::mongo::DB;
+use super::super::db::mongo::{Maintainer, MaintainerKind, PoolVisibility, DB};
+use super::autocomplete::autocomplete_pools;
+use super::checks::{author_editor, is_admin};
+use super::confirm::{confirm_import, finish_import};
+use crate::enforcement::{guild_bans, import_guild_bans as import_bans, issued_by_bot, list_users};
 use crate::{Context, Error};
-use futures::{Stream, StreamExt};
 use log::{error, info};
+use poise::serenity_prelude as serenity;
+use rand::rngs::OsRng;
+use rand::RngCore;
 use serenity::utils::Color;
+use std::collections::HashSet;
 
-#[poise::command(slash_command, subcommands("add", "remove", "list"))]
+#[poise::command(
+    slash_command,
+    subcommands(
+        "add",
+        "approval",
+        "import_guild_bans",
+        "invite",
+        "maintainers",
+        "owner",
+        "redeem",
+        "remove",
+        "list",
+        "visibility"
+    )
+)]
 pub async fn pool(_: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-async fn autocomplete_pools<'a>(
-    _: Context<'_>,
-    partial: &'a str,
-) -> impl Stream<Item = String> + 'a {
-    let db = DB::init().await.unwrap();
-    let pools = db.list_pools().await.unwrap_or(vec![]);
-
-    futures::stream::iter(pools)
-        .filter(move |pool| futures::future::ready(pool.pool_name.starts_with(partial)))
-        .map(|pool| pool.pool_name)
-}
-
-/// Create a new banpool
-#[poise::command(slash_command)]
+/// Create a new banpool owned by this guild
+#[poise::command(slash_command, guild_only, check = "is_admin")]
 pub async fn add(
     ctx: Context<'_>,
     #[description = "Banpool Name"] name: String,
     #[description = "Banpool Description"] description: String,
 ) -> Result<(), Error> {
     let db = DB::init().await.unwrap();
-    match db.add_pool(&name, &description).await {
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db.add_pool(&name, &description, &guild_id).await {
         Ok(_) => {
             info!("Added pool: {}", name);
             ctx.send(|r| {
@@ -61,14 +71,19 @@ pub async fn add(
 #[poise::command(slash_command)]
 pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
     let db = DB::init().await.unwrap();
-    match db.list_pools().await {
+    let guild_id = ctx.guild_id().map(|guild_id| guild_id.to_string());
+    match db.list_visible_pools(guild_id.as_deref()).await {
         Ok(pools) => {
             info!("Listed pools");
             // TODO: add Discord feedback
             let mut pool_fields: Vec<_> = Vec::new();
 
             for pool in pools {
-                pool_fields.push((pool.pool_name, pool.pool_desc, false));
+                let name = match pool.visibility {
+                    PoolVisibility::Public => pool.pool_name,
+                    visibility => format!("{} ({visibility:?})", pool.pool_name),
+                };
+                pool_fields.push((name, pool.pool_desc, false));
             }
             ctx.send(|r| {
                 r.embed(|r| {
@@ -85,7 +100,7 @@ pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
                 r.embed(|r| {
                     r.title("List Banpools")
                         .color(Color::RED)
-                        .description(format!("Failed to list Banpools"))
+                        .description("Failed to list Banpools")
                 })
             })
             .await?;
@@ -96,7 +111,7 @@ pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
 }
 
 /// Remove a target banpool by name
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "is_admin")]
 pub async fn remove(
     ctx: Context<'_>,
     #[autocomplete = "autocomplete_pools"]
@@ -104,7 +119,8 @@ pub async fn remove(
     name: String,
 ) -> Result<(), Error> {
     let db = DB::init().await.unwrap();
-    match db.delete_pool(&name).await {
+    let editor = author_editor(ctx).await?;
+    match db.delete_pool(&name, &editor).await {
         Ok(_) => {
             info!("Deleted pool: {}", name);
             ctx.send(|r| {
@@ -131,3 +147,533 @@ pub async fn remove(
         }
     }
 }
+
+/// Import this guild's existing Discord bans into a banpool
+#[poise::command(
+    slash_command,
+    guild_only,
+    rename = "import-guild-bans",
+    check = "is_admin"
+)]
+pub async fn import_guild_bans(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_pools"]
+    #[description = "Banpool Name"]
+    name: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap();
+    let editor = author_editor(ctx).await?;
+
+    // the preview reveals who is in the pool, so only its editors may see it
+    if let Err(e) = db.check_pool_access(&name, &editor, false).await {
+        ctx.send(|r| {
+            r.embed(|r| {
+                r.title("Import Guild Bans")
+                    .color(Color::RED)
+                    .description(format!("Unable to import into `{name}`:\n{e}"))
+            })
+        })
+        .await?;
+        return Err(e.into());
+    }
+
+    let guild_bans = match guild_bans(ctx.http(), guild_id).await {
+        Ok(bans) => bans,
+        Err(e) => {
+            error!("Failed to fetch bans of {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Import Guild Bans")
+                        .color(Color::RED)
+                        .description(format!("Unable to read this server's bans:\n{e}"))
+                })
+            })
+            .await?;
+            return Err(e.into());
+        }
+    };
+    let pooled: HashSet<String> = db
+        .get_pool_bans(&name)
+        .await?
+        .into_iter()
+        .map(|ban| ban.user_id)
+        .collect();
+    // the preview is built from exactly the bans that get imported
+    let total = guild_bans.len();
+    let (issued, own): (Vec<serenity::Ban>, Vec<serenity::Ban>) =
+        guild_bans.into_iter().partition(issued_by_bot);
+    let (new_bans, pooled_bans): (Vec<serenity::Ban>, Vec<serenity::Ban>) = own
+        .into_iter()
+        .partition(|ban| !pooled.contains(&ban.user.id.to_string()));
+    let new_users: Vec<String> = new_bans.iter().map(|ban| ban.user.id.to_string()).collect();
+
+    if new_users.is_empty() {
+        ctx.send(|r| {
+            r.embed(|r| {
+                r.title("Import Guild Bans")
+                    .color(Color::DARK_GREEN)
+                    .description(format!(
+                        "Every ban this server issued itself is already in `{name}`"
+                    ))
+            })
+        })
+        .await?;
+        return Ok(());
+    }
+
+    let reply = match confirm_import(ctx, "Import Guild Bans", |r| {
+        r.description(format!(
+            "{} of this server's {} bans will be added to `{name}`. {} are already in it and {} were issued by the bot for a pool.",
+            new_users.len(),
+            total,
+            pooled_bans.len(),
+            issued.len()
+        ))
+        .field("Users", list_users(&new_users, 1000), false)
+    })
+    .await?
+    {
+        Some(reply) => reply,
+        None => return Ok(()),
+    };
+
+    match import_bans(&db, &name, &new_bans, &editor).await {
+        Ok(added) => {
+            info!(
+                "Imported {} bans from {} into {}",
+                added.len(),
+                guild_id,
+                name
+            );
+            let description = format!("{} bans were imported into `{name}`", added.len());
+            finish_import(
+                ctx,
+                &reply,
+                "Import Guild Bans",
+                Color::DARK_GREEN,
+                &description,
+            )
+            .await
+        }
+        Err(e) => {
+            error!(
+                "Failed to import bans from {} into {}: {}",
+                guild_id, name, e
+            );
+            let description = format!("Failed to import bans into `{name}`:\n{e}");
+            finish_import(ctx, &reply, "Import Guild Bans", Color::RED, &description).await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Describes a maintainer entry for listings
+fn describe_maintainer(maintainer: &Maintainer) -> String {
+    match maintainer.kind {
+        MaintainerKind::Guild => format!("Server `{}`", maintainer.id),
+        MaintainerKind::Role => format!("Role <@&{}>", maintainer.id),
+        MaintainerKind::User => format!("User <@{}>", maintainer.id),
+    }
+}
+
+#[poise::command(
+    slash_command,
+    subcommands("maintainers_add", "maintainers_list", "maintainers_remove")
+)]
+pub async fn maintainers(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Let another server, role or user add and remove bans in a pool this guild owns
+#[poise::command(slash_command, guild_only, rename = "add", check = "is_admin")]
+pub async fn maintainers_add(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_pools"]
+    #[description = "Banpool Name"]
+    name: String,
+    #[description = "Maintainer Type"] kind: MaintainerKind,
+    #[description = "Server, Role or User ID"] id: String,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let editor = author_editor(ctx).await?;
+    let result = match id.parse::<u64>() {
+        Ok(_) => {
+            let maintainer = Maintainer {
+                kind,
+                id: id.clone(),
+            };
+            db.add_pool_maintainer(&name, &editor, maintainer).await
+        }
+        Err(_) => Err(anyhow::anyhow!("`{id}` is not a valid Discord ID")),
+    };
+    match result {
+        Ok(_) => {
+            info!("Added {:?} {} as maintainer of {}", kind, id, name);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Add Maintainer")
+                        .color(Color::DARK_GREEN)
+                        .description(format!(
+                            "{} can now maintain `{}`",
+                            describe_maintainer(&Maintainer { kind, id }),
+                            name
+                        ))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to add maintainer to {}: {}", name, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Add Maintainer")
+                        .color(Color::RED)
+                        .description(format!(
+                            "Failed to add a maintainer to `{}`.\n\n{}",
+                            name, e
+                        ))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Take away a maintainer's access to a pool this guild owns
+#[poise::command(slash_command, guild_only, rename = "remove", check = "is_admin")]
+pub async fn maintainers_remove(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_pools"]
+    #[description = "Banpool Name"]
+    name: String,
+    #[description = "Maintainer Type"] kind: MaintainerKind,
+    #[description = "Server, Role or User ID"] id: String,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let editor = author_editor(ctx).await?;
+    let maintainer = Maintainer {
+        kind,
+        id: id.clone(),
+    };
+    match db.delete_pool_maintainer(&name, &editor, maintainer).await {
+        Ok(_) => {
+            info!("Removed {:?} {} as maintainer of {}", kind, id, name);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Remove Maintainer")
+                        .color(Color::DARK_GREEN)
+                        .description(format!(
+                            "{} can no longer maintain `{}`",
+                            describe_maintainer(&Maintainer { kind, id }),
+                            name
+                        ))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to remove maintainer from {}: {}", name, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Remove Maintainer")
+                        .color(Color::RED)
+                        .description(format!(
+                            "Failed to remove a maintainer from `{}`.\n\n{}",
+                            name, e
+                        ))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// List who besides the owner may maintain a pool
+#[poise::command(slash_command, rename = "list")]
+pub async fn maintainers_list(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_pools"]
+    #[description = "Banpool Name"]
+    name: String,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().map(|guild_id| guild_id.to_string());
+    // private pools look the same as missing ones to guilds outside them
+    let pool = db.get_pool(&name).await.and_then(|pool| {
+        if pool.is_listed_for(guild_id.as_deref()) {
+            Ok(pool)
+        } else {
+            Err(anyhow::anyhow!("This pool does not exist"))
+        }
+    });
+    match pool {
+        Ok(pool) => {
+            let owner = match &pool.owner_id {
+                Some(owner_id) => format!("Owned by server `{owner_id}`"),
+                None => String::from("This pool has no owner and is read-only"),
+            };
+            let mut maintainer_string = String::new();
+            for maintainer in &pool.maintainers {
+                maintainer_string.push_str(&format!("{}\n", describe_maintainer(maintainer)));
+            }
+            if pool.maintainers.is_empty() {
+                maintainer_string.push_str("No maintainers have been added");
+            }
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("List Maintainers")
+                        .color(Color::DARK_GREEN)
+                        .description(format!("{owner}\n\n{maintainer_string}"))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to list maintainers of {}: {}", name, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("List Maintainers")
+                        .color(Color::RED)
+                        .description(format!(
+                            "Failed to list maintainers of `{}`.\n\n{}",
+                            name, e
+                        ))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Choose who can find and subscribe to a pool this guild owns
+#[poise::command(slash_command, guild_only, check = "is_admin")]
+pub async fn visibility(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_pools"]
+    #[description = "Banpool Name"]
+    name: String,
+    #[description = "Banpool Visibility"] visibility: PoolVisibility,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let editor = author_editor(ctx).await?;
+    match db.set_pool_visibility(&name, &editor, visibility).await {
+        Ok(_) => {
+            info!("Set visibility of {} to {:?}", name, visibility);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Banpool Visibility")
+                        .color(Color::DARK_GREEN)
+                        .description(format!("`{name}` is now {visibility:?}"))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to set visibility of {}: {}", name, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Banpool Visibility")
+                        .color(Color::RED)
+                        .description(format!(
+                            "Failed to set the visibility of `{}`.\n\n{}",
+                            name, e
+                        ))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Random code for a pool invite, read from the operating system's secure
+/// random number generator as invites are all that guards a private pool
+fn new_invite_code() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Create a single use invite that lets another server subscribe to a private pool
+#[poise::command(slash_command, guild_only, check = "is_admin")]
+pub async fn invite(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_pools"]
+    #[description = "Banpool Name"]
+    name: String,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let editor = author_editor(ctx).await?;
+    let code = new_invite_code();
+    match db.add_pool_invite(&name, &editor, &code).await {
+        Ok(_) => {
+            info!("Created an invite to {}", name);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Banpool Invite")
+                        .color(Color::DARK_GREEN)
+                        .description(format!(
+                            "Share this code with the server you want to invite to `{name}`. It can be used once with `/pool redeem`.\n\n`{code}`"
+                        ))
+                })
+                .ephemeral(true)
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to create an invite to {}: {}", name, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Banpool Invite")
+                        .color(Color::RED)
+                        .description(format!(
+                            "Failed to create an invite to `{}`.\n\n{}",
+                            name, e
+                        ))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Redeem a pool invite so this server can subscribe to a private pool
+#[poise::command(slash_command, guild_only, check = "is_admin")]
+pub async fn redeem(
+    ctx: Context<'_>,
+    #[description = "Invite Code"] code: String,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db.redeem_pool_invite(code.trim(), &guild_id).await {
+        Ok(name) => {
+            info!("{} redeemed an invite to {}", guild_id, name);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Redeem Invite")
+                        .color(Color::DARK_GREEN)
+                        .description(format!(
+                            "This server can now subscribe to `{name}` with `/subscription add`"
+                        ))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to redeem an invite on {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Redeem Invite")
+                        .color(Color::RED)
+                        .description(format!("Failed to redeem the invite.\n\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Hand a pool to another server, or give a pool without an owner one
+#[poise::command(slash_command, owners_only)]
+pub async fn owner(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_pools"]
+    #[description = "Banpool Name"]
+    name: String,
+    #[description = "ID of the server that should own the pool"] server_id: String,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let result = match server_id.trim().parse::<u64>() {
+        Ok(server_id) => db.set_pool_owner(&name, &server_id.to_string()).await,
+        Err(_) => Err(anyhow::anyhow!("`{server_id}` is not a valid server ID")),
+    };
+    match result {
+        Ok(_) => {
+            info!("Set the owner of {} to {}", name, server_id);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Banpool Owner")
+                        .color(Color::DARK_GREEN)
+                        .description(format!("`{name}` is now owned by server `{server_id}`"))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to set the owner of {}: {}", name, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Banpool Owner")
+                        .color(Color::RED)
+                        .description(format!(
+                            "Failed to change the owner of `{}`.\n\n{}",
+                            name, e
+                        ))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Choose whether subscriptions to a pool this guild owns need approval
+#[poise::command(slash_command, guild_only, check = "is_admin")]
+pub async fn approval(
+    ctx: Context<'_>,
+    #[autocomplete = "autocomplete_pools"]
+    #[description = "Banpool Name"]
+    name: String,
+    #[description = "Require approval for new subscriptions"] required: bool,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let editor = author_editor(ctx).await?;
+    match db.set_pool_approval(&name, &editor, required).await {
+        Ok(_) => {
+            info!("Set {} to require approval: {}", name, required);
+            let description = if required {
+                format!("New subscriptions to `{name}` need this server's approval")
+            } else {
+                format!("Any server can now subscribe to `{name}` without approval")
+            };
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Subscription Approval")
+                        .color(Color::DARK_GREEN)
+                        .description(description)
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to set approval on {}: {}", name, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Subscription Approval")
+                        .color(Color::RED)
+                        .description(format!(
+                            "Failed to change approval for `{}`.\n\n{}",
+                            name, e
+                        ))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
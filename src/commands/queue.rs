@@ -0,0 +1,43 @@
+use super::super::db::mongo::{JobStatus, DB};
+use crate::{Context, Error};
+use log::error;
+use serenity::utils::Color;
+
+#[poise::command(slash_command, subcommands("status"))]
+pub async fn queue(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show how many enforcement jobs are waiting to be processed
+#[poise::command(slash_command)]
+pub async fn status(ctx: Context<'_>) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let mut counts = Vec::new();
+    for status in [JobStatus::Pending, JobStatus::Running, JobStatus::Failed] {
+        match db.count_jobs(status).await {
+            Ok(count) => counts.push((format!("{status:?}"), count.to_string(), true)),
+            Err(e) => {
+                error!("Unable to count {:?} jobs: {}", status, e);
+                ctx.send(|r| {
+                    r.embed(|r| {
+                        r.title("Enforcement Queue")
+                            .color(Color::RED)
+                            .description(format!("Failed to fetch the queue depth:\n{e}"))
+                    })
+                })
+                .await?;
+                return Err(e.into());
+            }
+        }
+    }
+
+    ctx.send(|r| {
+        r.embed(|r| {
+            r.title("Enforcement Queue")
+                .color(Color::DARK_GREEN)
+                .fields(counts)
+        })
+    })
+    .await?;
+    Ok(())
+}
@@ -1,27 +1,503 @@
-use super::super::db::mongo;
+use super::super::db::mongo::{
+    Ban, JobKind, Subscription, SubscriptionLevel, SubscriptionStatus, DB,
+};
+use super::autocomplete::autocomplete_pools;
+use super::checks::{author_editor, is_admin};
+use crate::enforcement::approval::request_approval;
+use crate::enforcement::queue::enqueue_guild;
+use crate::enforcement::reconcile::find_drift;
+use crate::enforcement::{import_guild_bans, issued_by_bot, list_users, MemberSweep};
 use crate::{Context, Error};
+use log::{error, info};
+use poise::serenity_prelude as serenity;
+use serenity::utils::Color;
+use std::time::Duration;
 
-#[poise::command(slash_command, subcommands("add", "remove"))]
+// Embed field values are limited to 1024 characters
+const MAX_FIELD_LENGTH: usize = 1000;
+
+#[poise::command(slash_command, subcommands("add", "diff", "list", "remove", "sweep"))]
 pub async fn subscription(_: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-#[poise::command(slash_command)]
-pub async fn add(ctx: Context<'_>) -> Result<(), Error> {
-    Ok(())
+/// Subscribe this guild to a banpool
+#[poise::command(slash_command, guild_only, check = "is_admin")]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "Banpool Name"]
+    #[autocomplete = "autocomplete_pools"]
+    pool: String,
+    #[description = "What to do with members of the pool"] level: SubscriptionLevel,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db
+        .add_subscription(&pool, &guild_id, &ctx.author().id.to_string(), level)
+        .await
+    {
+        Ok(SubscriptionStatus::Pending) => {
+            info!(
+                "Requested {} subscribing to {} with {:?}",
+                guild_id, pool, level
+            );
+            let author_id = ctx.author().id.to_string();
+            if let Err(e) =
+                request_approval(ctx.http(), &db, &guild_id, &pool, level, &author_id).await
+            {
+                error!(
+                    "Failed to request approval for {} to {}: {}",
+                    guild_id, pool, e
+                );
+                // nobody was asked, so the request must not linger as pending
+                if let Err(e) = db.delete_subscription(&pool, &guild_id).await {
+                    error!(
+                        "Failed to withdraw request of {} to {}: {}",
+                        guild_id, pool, e
+                    );
+                }
+                ctx.send(|r| {
+                    r.embed(|r| {
+                        r.title("Create Subscription")
+                            .color(Color::RED)
+                            .description(format!(
+                                "`{pool}` requires approval, but its owner could not be asked:\n{e}"
+                            ))
+                    })
+                })
+                .await?;
+                return Err(e.into());
+            }
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Create Subscription")
+                        .color(Color::ORANGE)
+                        .description(format!(
+                            "`{pool}` requires approval from its owner. This server will be notified once the request is decided."
+                        ))
+                })
+            })
+            .await?;
+            return Ok(());
+        }
+        Ok(_) => {
+            info!("Subscribed {} to {} with {:?}", guild_id, pool, level);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Create Subscription")
+                        .color(Color::DARK_GREEN)
+                        .description(format!(
+                            "This server is now subscribed to `{pool}` with level {level:?}"
+                        ))
+                })
+            })
+            .await?;
+        }
+        Err(e) => {
+            error!("Failed to subscribe {} to {}: {}", guild_id, pool, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Create Subscription")
+                        .color(Color::RED)
+                        .description(format!("Failed to subscribe to `{pool}`:\n{e}"))
+                })
+            })
+            .await?;
+            return Err(e.into());
+        }
+    }
+
+    // report members that were in the pool before the guild subscribed
+    let subscription = db.get_subscription(&pool, &guild_id).await?;
+    sweep_members(ctx, &db, subscription, false).await
 }
 
-#[poise::command(slash_command)]
-pub async fn diff(ctx: Context<'_>) -> Result<(), Error> {
+/// Compare a pool with this guild's bans
+#[poise::command(slash_command, guild_only, check = "is_admin")]
+pub async fn diff(
+    ctx: Context<'_>,
+    #[description = "Banpool Name"]
+    #[autocomplete = "autocomplete_pools"]
+    pool: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+
+    let drift = match find_drift(ctx.http(), &db, &guild_id, &pool).await {
+        Ok(drift) => drift,
+        Err(e) => {
+            error!("Failed to diff {} against {}: {}", pool, guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Subscription Diff")
+                        .color(Color::RED)
+                        .description(format!("Unable to compare {pool} with this server:\n{e}"))
+                })
+            })
+            .await?;
+            return Err(e.into());
+        }
+    };
+
+    let missing: Vec<String> = drift
+        .missing
+        .iter()
+        .map(|ban| ban.user_id.clone())
+        .collect();
+    let unpooled: Vec<String> = drift
+        .unpooled
+        .iter()
+        .map(|ban| ban.user.id.to_string())
+        .collect();
+    let excepted: Vec<String> = drift
+        .excepted
+        .iter()
+        .map(|ban| ban.user_id.clone())
+        .collect();
+    let section = |users: &[String]| {
+        if users.is_empty() {
+            String::from("None")
+        } else {
+            list_users(users, MAX_FIELD_LENGTH)
+        }
+    };
+    // missing bans are applied through the subscription, so it has to exist
+    let subscribed = db.get_subscription(&pool, &guild_id).await.is_ok();
+
+    let editor = author_editor(ctx).await?;
+    let uuid = ctx.id();
+    let apply_id = format!("{uuid}-apply");
+    let contribute_id = format!("{uuid}-contribute");
+    ctx.send(|r| {
+        r.embed(|r| {
+            r.title("Subscription Diff")
+                .color(Color::DARK_GREEN)
+                .description(format!("Comparing {pool} with this server's bans"))
+                .field(
+                    format!("In the pool but not banned here ({})", missing.len()),
+                    section(&missing),
+                    false,
+                )
+                .field(
+                    format!("Banned here but not in the pool ({})", unpooled.len()),
+                    section(&unpooled),
+                    false,
+                )
+                .field(
+                    format!("Excepted ({})", excepted.len()),
+                    section(&excepted),
+                    false,
+                )
+        })
+        .components(|c| {
+            let apply = subscribed && !missing.is_empty();
+            // bans the bot issued for pools are never contributed
+            let contribute = drift.unpooled.iter().any(|ban| !issued_by_bot(ban));
+            if apply || contribute {
+                c.create_action_row(|row| {
+                    if apply {
+                        row.create_button(|b| {
+                            b.style(serenity::ButtonStyle::Danger)
+                                .label("Apply missing bans")
+                                .custom_id(&apply_id)
+                        });
+                    }
+                    if contribute {
+                        row.create_button(|b| {
+                            b.style(serenity::ButtonStyle::Primary)
+                                .label("Contribute local bans")
+                                .custom_id(&contribute_id)
+                        });
+                    }
+                    row
+                });
+            }
+            c
+        })
+    })
+    .await?;
+
+    while let Some(mci) = serenity::CollectComponentInteraction::new(ctx)
+        .author_id(ctx.author().id)
+        .channel_id(ctx.channel_id())
+        .timeout(Duration::from_secs(120))
+        .filter(move |mci| mci.data.custom_id.starts_with(&uuid.to_string()))
+        .await
+    {
+        mci.create_interaction_response(ctx, |ir| {
+            ir.kind(serenity::InteractionResponseType::DeferredUpdateMessage)
+        })
+        .await?;
+
+        let result = if mci.data.custom_id == apply_id {
+            apply_missing(&db, &guild_id, &pool, &drift.missing).await
+        } else {
+            import_guild_bans(&db, &pool, &drift.unpooled, &editor)
+                .await
+                .map(|added| format!("Added {} of this server's bans to {pool}.", added.len()))
+        };
+        let (color, description) = match result {
+            Ok(description) => (Color::DARK_GREEN, description),
+            Err(e) => {
+                error!("Failed to act on diff of {} in {}: {}", pool, guild_id, e);
+                (Color::RED, format!("Failed to update {pool}:\n{e}"))
+            }
+        };
+        mci.create_followup_message(ctx, |m| {
+            m.embed(|e| {
+                e.title("Subscription Diff")
+                    .color(color)
+                    .description(description)
+            })
+        })
+        .await?;
+    }
+
     Ok(())
 }
 
-#[poise::command(slash_command)]
+/// Queues the guild's subscription level for pool members it hasn't banned
+async fn apply_missing(
+    db: &DB,
+    guild_id: &str,
+    pool: &str,
+    missing: &[Ban],
+) -> anyhow::Result<String> {
+    let mut queued = 0;
+    for ban in missing {
+        if enqueue_guild(
+            db,
+            JobKind::Enforce,
+            guild_id,
+            &ban.user_id,
+            pool,
+            &ban.reason,
+        )
+        .await?
+        {
+            queued += 1;
+        }
+    }
+    info!("Queued {} missing {} bans in {}", queued, pool, guild_id);
+    Ok(format!(
+        "Queued {queued} missing bans. Use `/queue status` to follow their progress."
+    ))
+}
+
+/// List this guild's subscriptions
+#[poise::command(slash_command, guild_only)]
 pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
-    Ok(())
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db.list_subscriptions(&guild_id).await {
+        Ok(subscriptions) => {
+            info!("Listed subscriptions on guild {}", guild_id);
+            // embeds hold at most 25 fields
+            let subscription_fields: Vec<_> = subscriptions
+                .iter()
+                .take(25)
+                .map(|subscription| {
+                    (
+                        subscription.pool_name.clone(),
+                        format!(
+                            "Level: {:?}\nCreated by <@{}> on <t:{}:D>{}",
+                            subscription.subscription_level,
+                            subscription.creator_id,
+                            subscription.timestamp.timestamp(),
+                            match subscription.status {
+                                SubscriptionStatus::Approved => String::new(),
+                                status => format!("\nStatus: {status:?}"),
+                            }
+                        ),
+                        false,
+                    )
+                })
+                .collect();
+            let description = if subscriptions.is_empty() {
+                "This server is not subscribed to any pools"
+            } else {
+                "This server is subscribed to these pools:"
+            };
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("List Subscriptions")
+                        .color(Color::DARK_GREEN)
+                        .description(description)
+                        .fields(subscription_fields)
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Unable to list subscriptions of {}: {}", guild_id, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("List Subscriptions")
+                        .color(Color::RED)
+                        .description(format!("Failed to list subscriptions:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
+}
+
+/// Unsubscribe this guild from a banpool
+#[poise::command(slash_command, guild_only, check = "is_admin")]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Banpool Name"]
+    #[autocomplete = "autocomplete_pools"]
+    pool: String,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    match db.delete_subscription(&pool, &guild_id).await {
+        Ok(_) => {
+            info!("Unsubscribed {} from {}", guild_id, pool);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Remove Subscription")
+                        .color(Color::DARK_GREEN)
+                        .description(format!("This server is no longer subscribed to `{pool}`"))
+                })
+            })
+            .await?;
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to unsubscribe {} from {}: {}", guild_id, pool, e);
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Remove Subscription")
+                        .color(Color::RED)
+                        .description(format!("Failed to unsubscribe from `{pool}`:\n{e}"))
+                })
+            })
+            .await?;
+            Err(e.into())
+        }
+    }
 }
 
-#[poise::command(slash_command)]
-pub async fn remove(ctx: Context<'_>) -> Result<(), Error> {
+/// Check this guild's existing members against a subscribed pool
+#[poise::command(slash_command, guild_only, check = "is_admin")]
+pub async fn sweep(
+    ctx: Context<'_>,
+    #[description = "Banpool Name"]
+    #[autocomplete = "autocomplete_pools"]
+    pool: String,
+    #[description = "Apply the subscription level to matches"] apply: Option<bool>,
+) -> Result<(), Error> {
+    let db = DB::init().await.unwrap();
+    let guild_id = ctx.guild_id().unwrap().to_string();
+    let apply = apply.unwrap_or(false);
+
+    let subscription = match db.get_subscription(&pool, &guild_id).await {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            ctx.send(|r| {
+                r.embed(|r| {
+                    r.title("Sweep Members")
+                        .color(Color::RED)
+                        .description(format!("Unable to sweep {pool}:\n{e}"))
+                })
+            })
+            .await?;
+            return Err(e.into());
+        }
+    };
+
+    sweep_members(ctx, &db, subscription, apply).await
+}
+
+/// Runs a member sweep for the subscription, keeping a reply updated with its
+/// progress
+async fn sweep_members(
+    ctx: Context<'_>,
+    db: &DB,
+    subscription: Subscription,
+    apply: bool,
+) -> Result<(), Error> {
+    let guild_id = subscription.server_id.clone();
+    let pool = subscription.pool_name.clone();
+    let reply = ctx
+        .send(|r| {
+            r.embed(|r| {
+                r.title("Sweep Members")
+                    .color(Color::BLUE)
+                    .description(format!("Checking members against {pool}..."))
+            })
+        })
+        .await?;
+
+    let mut sweep = MemberSweep::new(db, subscription).await?;
+    while !sweep.is_done() {
+        if let Err(e) = sweep.next_page(ctx.http(), db, apply).await {
+            error!(
+                "Member sweep of {} against {} failed: {}",
+                guild_id, pool, e
+            );
+            reply
+                .edit(ctx, |r| {
+                    r.embed(|r| {
+                        r.title("Sweep Members")
+                            .color(Color::RED)
+                            .description(format!(
+                                "Sweep stopped after {} members:\n{e}",
+                                sweep.scanned
+                            ))
+                    })
+                })
+                .await?;
+            return Err(e.into());
+        }
+
+        reply
+            .edit(ctx, |r| {
+                r.embed(|r| {
+                    r.title("Sweep Members")
+                        .color(Color::BLUE)
+                        .description(format!(
+                            "Checking members against {pool}...\nScanned {} members, {} matches",
+                            sweep.scanned,
+                            sweep.matches.len()
+                        ))
+                })
+            })
+            .await?;
+    }
+
+    info!(
+        "Swept {} members of {} against {}, {} matches",
+        sweep.scanned,
+        guild_id,
+        pool,
+        sweep.matches.len()
+    );
+    let mut summary = format!(
+        "Scanned {} members, {} are in {pool}.",
+        sweep.scanned,
+        sweep.matches.len()
+    );
+    if !apply && !sweep.matches.is_empty() {
+        match sweep.report(ctx.http(), db).await {
+            Ok(_) => summary.push_str("\nA report was posted to the notification channel."),
+            Err(e) => summary.push_str(&format!("\nUnable to post the report:\n{e}")),
+        }
+    }
+
+    reply
+        .edit(ctx, |r| {
+            r.embed(|r| {
+                r.title("Sweep Members")
+                    .color(Color::DARK_GREEN)
+                    .description(summary)
+            })
+        })
+        .await?;
     Ok(())
 }
@@ -7,12 +7,22 @@ use anyhow::{anyhow, Result};
 use chrono::Utc;
 use dotenv::dotenv;
 use log::info;
-use mongodb::bson::doc;
-use mongodb::{options::ClientOptions, Client};
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use mongodb::{
+    options::{
+        ClientOptions, FindOneAndUpdateOptions, FindOneOptions, FindOptions, ReturnDocument,
+        UpdateOptions,
+    },
+    Client,
+};
 use serde::{Deserialize, Serialize};
 use serenity::futures::TryStreamExt;
 use std::env;
 
+// Actions of the account age gate are logged under this name, so no pool may
+// take it
+pub const ACCOUNT_AGE_GATE: &str = "account-age";
+
 #[derive(Clone, Debug)]
 pub struct DB {
     pub client: Client,
@@ -23,20 +33,153 @@ pub struct DB {
 pub struct BanPool {
     pub pool_name: String,
     pub pool_desc: String,
+    // guild that created the pool, pools from before ownership have none and
+    // stay read-only until a bot owner assigns them one
+    #[serde(default)]
+    pub owner_id: Option<String>,
+    // besides the owner, these may add and remove bans
+    #[serde(default)]
+    pub maintainers: Vec<Maintainer>,
+    #[serde(default)]
+    pub visibility: PoolVisibility,
+    // guilds that redeemed an invite to the pool
+    #[serde(default)]
+    pub invited_guilds: Vec<String>,
+    // subscriptions wait for the owner's approval
+    #[serde(default)]
+    pub requires_approval: bool,
     #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
     pub timestamp: chrono::DateTime<Utc>,
 }
 
+/// Who can find and subscribe to a pool
+#[derive(
+    Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolVisibility {
+    // listed, anyone can subscribe
+    #[default]
+    #[name = "Public"]
+    Public,
+    // not listed, anyone who knows the name can subscribe
+    #[name = "Unlisted"]
+    Unlisted,
+    // not listed, subscribing takes an invite from the owner
+    #[name = "Private"]
+    Private,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PoolInvite {
+    pub code: String,
+    pub pool_name: String,
+    pub creator_id: String,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub timestamp: chrono::DateTime<Utc>,
+}
+
+/// What a pool maintainer entry refers to
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintainerKind {
+    #[name = "Guild"]
+    Guild,
+    #[name = "Role"]
+    Role,
+    #[name = "User"]
+    User,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Maintainer {
+    pub kind: MaintainerKind,
+    pub id: String,
+}
+
+/// The guild, user and roles behind a change to a pool
+#[derive(Debug)]
+pub struct PoolEditor {
+    pub server_id: String,
+    pub user_id: String,
+    pub role_ids: Vec<String>,
+}
+
+impl BanPool {
+    /// Whether the editor is acting for the guild that owns the pool
+    pub fn is_owner(&self, editor: &PoolEditor) -> bool {
+        self.owner_id.as_deref() == Some(&editor.server_id)
+    }
+
+    /// Whether the guild owns, maintains or was invited to the pool
+    pub fn has_member_guild(&self, server_id: &str) -> bool {
+        self.owner_id.as_deref() == Some(server_id)
+            || self.invited_guilds.iter().any(|guild| guild == server_id)
+            || self.maintainers.iter().any(|maintainer| {
+                maintainer.kind == MaintainerKind::Guild && maintainer.id == server_id
+            })
+    }
+
+    /// Whether the pool shows up in listings and autocomplete for the guild
+    pub fn is_listed_for(&self, server_id: Option<&str>) -> bool {
+        self.visibility == PoolVisibility::Public
+            || server_id.is_some_and(|server_id| self.has_member_guild(server_id))
+    }
+
+    /// Whether the guild may subscribe to the pool
+    pub fn can_subscribe(&self, server_id: &str) -> bool {
+        self.visibility != PoolVisibility::Private || self.has_member_guild(server_id)
+    }
+
+    /// Whether the editor may add and remove bans in the pool
+    pub fn can_edit(&self, editor: &PoolEditor) -> bool {
+        self.is_owner(editor)
+            || self
+                .maintainers
+                .iter()
+                .any(|maintainer| match maintainer.kind {
+                    MaintainerKind::Guild => maintainer.id == editor.server_id,
+                    MaintainerKind::Role => editor.role_ids.contains(&maintainer.id),
+                    MaintainerKind::User => maintainer.id == editor.user_id,
+                })
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Ban {
     pub user_id: String,
     pub pool_name: String,
     pub reason: String,
     pub creator_id: String,
+    // temporary bans are removed from the pool once they expire
+    #[serde(
+        default,
+        with = "bson::serde_helpers::chrono_datetime_as_bson_datetime_optional"
+    )]
+    pub expires_at: Option<chrono::DateTime<Utc>>,
+    // overrides how many days of messages subscribed guilds purge on ban
+    #[serde(default)]
+    pub delete_message_days: Option<u8>,
+    // the user's profile when they were banned, in case they rename or leave
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub discriminator: Option<u16>,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
     #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
     pub timestamp: chrono::DateTime<Utc>,
 }
 
+/// The user a new ban targets and the profile they had when they were banned
+#[derive(Debug, Clone)]
+pub struct BanTarget {
+    pub user_id: String,
+    pub username: Option<String>,
+    pub discriminator: Option<u16>,
+    pub avatar_url: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BanException {
     pub user_id: String,
@@ -46,12 +189,128 @@ pub struct BanException {
     pub timestamp: chrono::DateTime<Utc>,
 }
 
+/// What a subscribing guild does with members of the pool
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionLevel {
+    #[name = "Notify only"]
+    NotifyOnly,
+    #[name = "Auto-ban"]
+    AutoBan,
+    #[name = "Auto-kick"]
+    AutoKick,
+    #[name = "Quarantine"]
+    Quarantine,
+    #[name = "Timeout"]
+    Timeout,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Subscription {
     pub pool_name: String,
-    pub subscription_level: String,
+    pub subscription_level: SubscriptionLevel,
     pub server_id: String,
     pub creator_id: String,
+    // subscriptions to pools that require approval wait for the owner
+    #[serde(default)]
+    pub status: SubscriptionStatus,
+    #[serde(default)]
+    pub approver_id: Option<String>,
+    #[serde(
+        default,
+        with = "bson::serde_helpers::chrono_datetime_as_bson_datetime_optional"
+    )]
+    pub decided_at: Option<chrono::DateTime<Utc>>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub timestamp: chrono::DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    Pending,
+    #[default]
+    Approved,
+    Denied,
+}
+
+/// Narrows a subscription query down to subscriptions that are enforced
+fn active_subscriptions(mut filter: Document) -> Document {
+    // subscriptions from before approvals have no status and count as approved
+    filter.insert("status", doc! {"$nin": ["pending", "denied"]});
+    filter
+}
+
+/// A Discord action the bot took in a guild because of a pool ban
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Ban,
+    Kick,
+    Notify,
+    Unban,
+    Quarantine,
+    Release,
+    Timeout,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionResult {
+    Success,
+    Failure,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EnforcementAction {
+    pub server_id: String,
+    pub user_id: String,
+    pub pool_name: String,
+    pub action: ActionKind,
+    pub result: ActionResult,
+    pub error: Option<String>,
+    // when a timeout applied by this action runs out
+    #[serde(
+        default,
+        with = "bson::serde_helpers::chrono_datetime_as_bson_datetime_optional"
+    )]
+    pub expires_at: Option<chrono::DateTime<Utc>>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub timestamp: chrono::DateTime<Utc>,
+}
+
+/// The work a queued enforcement job performs in its guild
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    // apply the guild's subscription level to a new pool ban
+    Enforce,
+    // lift the guild ban of a user removed from the pool
+    Lift,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EnforcementJob {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub kind: JobKind,
+    pub server_id: String,
+    pub user_id: String,
+    pub pool_name: String,
+    pub reason: String,
+    pub status: JobStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub next_attempt: chrono::DateTime<Utc>,
     #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
     pub timestamp: chrono::DateTime<Utc>,
 }
@@ -59,8 +318,23 @@ pub struct Subscription {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ServerConfig {
     pub server_id: String,
-    pub announce_channel_id: String,
-    pub admin_role_id: String,
+    // only report what enforcement would have done instead of acting
+    #[serde(default)]
+    pub shadow_mode: bool,
+    // how long timeout subscriptions mute pool members for
+    #[serde(default)]
+    pub timeout_minutes: Option<i64>,
+    // fix drift found by the reconciler instead of only reporting it
+    #[serde(default)]
+    pub reconcile_repair: bool,
+    // accounts younger than this many days are handled on join, pool or not
+    #[serde(default)]
+    pub min_account_age_days: Option<i64>,
+    #[serde(default)]
+    pub account_age_action: Option<SubscriptionLevel>,
+    // days of messages purged when banning pool members, unless the ban says otherwise
+    #[serde(default)]
+    pub delete_message_days: Option<u8>,
     pub author_id: String,
     #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
     pub timestamp: chrono::DateTime<Utc>,
@@ -75,6 +349,27 @@ pub struct AdminRole {
     pub timestamp: chrono::DateTime<Utc>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QuarantineRole {
+    pub server_id: String,
+    pub role_id: String,
+    pub author_id: String,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub timestamp: chrono::DateTime<Utc>,
+}
+
+/// A member placed in quarantine because of a pool, along with the roles that
+/// were taken away so they can be given back
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QuarantinedMember {
+    pub server_id: String,
+    pub user_id: String,
+    pub pool_name: String,
+    pub removed_roles: Vec<String>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub timestamp: chrono::DateTime<Utc>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct NotificationChannel {
     pub server_id: String,
@@ -93,11 +388,15 @@ impl DB {
 
         Ok(Self {
             client: Client::with_options(client_options)?,
-            db_name: db_name,
+            db_name,
         })
     }
 
-    pub async fn add_pool(&self, pool_name: &str, pool_desc: &str) -> Result<()> {
+    pub async fn add_pool(&self, pool_name: &str, pool_desc: &str, owner_id: &str) -> Result<()> {
+        if pool_name.eq_ignore_ascii_case(ACCOUNT_AGE_GATE) {
+            return Err(anyhow!("`{pool_name}` is a reserved name"));
+        }
+
         // get the banpools collection
         let banpools = self
             .client
@@ -111,26 +410,32 @@ impl DB {
 
         // if it doesnt exist already, create it
         match pool {
-            Some(pool) => Err(anyhow!("Pool already exists.")),
+            Some(_) => Err(anyhow!("Pool already exists.")),
             None => {
                 info!("Creating {} with the description {}", pool_name, pool_desc);
                 let new_pool = BanPool {
                     pool_name: pool_name.to_string(),
                     pool_desc: pool_desc.to_string(),
+                    owner_id: Some(owner_id.to_string()),
+                    maintainers: vec![],
+                    visibility: PoolVisibility::Public,
+                    invited_guilds: vec![],
+                    requires_approval: false,
                     timestamp: Utc::now(),
                 };
-                let insert_result = banpools.insert_one(new_pool, None).await.unwrap();
+                banpools.insert_one(new_pool, None).await.unwrap();
                 Ok(())
             }
         }
     }
 
-    pub async fn delete_pool(&self, pool_name: &str) -> Result<()> {
+    pub async fn delete_pool(&self, pool_name: &str, editor: &PoolEditor) -> Result<()> {
         // get the banpools collection
         let banpools = self
             .client
             .database(&self.db_name)
             .collection::<BanPool>("banpools");
+        self.check_pool_access(pool_name, editor, true).await?;
         // check to see if the pool exists
         let pool = banpools
             .delete_one(doc! {"pool_name": pool_name}, None)
@@ -148,6 +453,225 @@ impl DB {
         }
     }
 
+    pub async fn get_pool(&self, pool_name: &str) -> Result<BanPool> {
+        let banpools = self
+            .client
+            .database(&self.db_name)
+            .collection::<BanPool>("banpools");
+
+        banpools
+            .find_one(doc! {"pool_name": pool_name}, None)
+            .await?
+            .ok_or_else(|| anyhow!("This pool does not exist"))
+    }
+
+    /// Fails unless the editor may change the pool's bans, or with `owner_only`
+    /// the pool itself
+    pub async fn check_pool_access(
+        &self,
+        pool_name: &str,
+        editor: &PoolEditor,
+        owner_only: bool,
+    ) -> Result<BanPool> {
+        let pool = self.get_pool(pool_name).await?;
+        if pool.owner_id.is_none() && !pool.can_edit(editor) {
+            Err(anyhow!(
+                "{pool_name} has no owner and is read-only until the bot's owner assigns one"
+            ))
+        } else if owner_only && !pool.is_owner(editor) {
+            Err(anyhow!(
+                "Only the server that owns {pool_name} can make this change"
+            ))
+        } else if !pool.can_edit(editor) {
+            Err(anyhow!("You are not a maintainer of {pool_name}"))
+        } else {
+            Ok(pool)
+        }
+    }
+
+    /// Hands the pool to another guild, which is also how pools from before
+    /// ownership get an owner
+    pub async fn set_pool_owner(&self, pool_name: &str, owner_id: &str) -> Result<()> {
+        let banpools = self
+            .client
+            .database(&self.db_name)
+            .collection::<BanPool>("banpools");
+        self.get_pool(pool_name).await?;
+
+        info!("Setting the owner of {} to {}", pool_name, owner_id);
+        banpools
+            .update_one(
+                doc! {"pool_name": pool_name},
+                doc! {"$set": {"owner_id": owner_id}},
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_pool_approval(
+        &self,
+        pool_name: &str,
+        editor: &PoolEditor,
+        requires_approval: bool,
+    ) -> Result<()> {
+        let banpools = self
+            .client
+            .database(&self.db_name)
+            .collection::<BanPool>("banpools");
+        self.check_pool_access(pool_name, editor, true).await?;
+
+        info!(
+            "Setting {} to require approval: {}",
+            pool_name, requires_approval
+        );
+        banpools
+            .update_one(
+                doc! {"pool_name": pool_name},
+                doc! {"$set": {"requires_approval": requires_approval}},
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_pool_visibility(
+        &self,
+        pool_name: &str,
+        editor: &PoolEditor,
+        visibility: PoolVisibility,
+    ) -> Result<()> {
+        let banpools = self
+            .client
+            .database(&self.db_name)
+            .collection::<BanPool>("banpools");
+        self.check_pool_access(pool_name, editor, true).await?;
+
+        info!("Setting {} visibility to {:?}", pool_name, visibility);
+        banpools
+            .update_one(
+                doc! {"pool_name": pool_name},
+                doc! {"$set": {"visibility": bson::to_bson(&visibility)?}},
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Stores a single use invite to the pool
+    pub async fn add_pool_invite(
+        &self,
+        pool_name: &str,
+        editor: &PoolEditor,
+        code: &str,
+    ) -> Result<()> {
+        let invites = self
+            .client
+            .database(&self.db_name)
+            .collection::<PoolInvite>("invites");
+        self.check_pool_access(pool_name, editor, true).await?;
+
+        info!("Creating an invite to {}", pool_name);
+        let invite = PoolInvite {
+            code: code.to_string(),
+            pool_name: pool_name.to_string(),
+            creator_id: editor.user_id.clone(),
+            timestamp: Utc::now(),
+        };
+        invites.insert_one(invite, None).await?;
+        Ok(())
+    }
+
+    /// Uses up an invite, letting the guild subscribe to its pool. Returns the
+    /// name of the pool.
+    pub async fn redeem_pool_invite(&self, code: &str, server_id: &str) -> Result<String> {
+        let invites = self
+            .client
+            .database(&self.db_name)
+            .collection::<PoolInvite>("invites");
+        let banpools = self
+            .client
+            .database(&self.db_name)
+            .collection::<BanPool>("banpools");
+
+        let invite = invites
+            .find_one_and_delete(doc! {"code": code}, None)
+            .await?
+            .ok_or_else(|| anyhow!("This invite does not exist or was already used"))?;
+
+        info!("{} redeemed an invite to {}", server_id, invite.pool_name);
+        banpools
+            .update_one(
+                doc! {"pool_name": &invite.pool_name},
+                doc! {"$addToSet": {"invited_guilds": server_id}},
+                None,
+            )
+            .await?;
+        Ok(invite.pool_name)
+    }
+
+    pub async fn add_pool_maintainer(
+        &self,
+        pool_name: &str,
+        editor: &PoolEditor,
+        maintainer: Maintainer,
+    ) -> Result<()> {
+        let banpools = self
+            .client
+            .database(&self.db_name)
+            .collection::<BanPool>("banpools");
+        let pool = self.check_pool_access(pool_name, editor, true).await?;
+        if pool.maintainers.contains(&maintainer) {
+            return Err(anyhow!("This maintainer was already added"));
+        }
+
+        info!("Adding {:?} as maintainer of {}", maintainer, pool_name);
+        banpools
+            .update_one(
+                doc! {"pool_name": pool_name},
+                doc! {"$push": {"maintainers": bson::to_bson(&maintainer)?}},
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_pool_maintainer(
+        &self,
+        pool_name: &str,
+        editor: &PoolEditor,
+        maintainer: Maintainer,
+    ) -> Result<()> {
+        let banpools = self
+            .client
+            .database(&self.db_name)
+            .collection::<BanPool>("banpools");
+        let pool = self.check_pool_access(pool_name, editor, true).await?;
+        if !pool.maintainers.contains(&maintainer) {
+            return Err(anyhow!("This maintainer does not exist"));
+        }
+
+        info!("Removing {:?} as maintainer of {}", maintainer, pool_name);
+        banpools
+            .update_one(
+                doc! {"pool_name": pool_name},
+                doc! {"$pull": {"maintainers": bson::to_bson(&maintainer)?}},
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Lists the pools the guild can see, or only public pools outside of guilds
+    pub async fn list_visible_pools(&self, server_id: Option<&str>) -> Result<Vec<BanPool>> {
+        Ok(self
+            .list_pools()
+            .await?
+            .into_iter()
+            .filter(|pool| pool.is_listed_for(server_id))
+            .collect())
+    }
+
     pub async fn list_pools(&self) -> Result<Vec<BanPool>> {
         // get the banpools collection
         let banpools = self
@@ -164,10 +688,12 @@ impl DB {
 
     pub async fn add_ban(
         &self,
-        user_id: &str,
+        target: &BanTarget,
         pool_name: &str,
         reason: &str,
-        author_id: &str,
+        editor: &PoolEditor,
+        expires_at: Option<chrono::DateTime<Utc>>,
+        delete_message_days: Option<u8>,
     ) -> Result<()> {
         // get the banpools collection
         let bans = self
@@ -178,14 +704,18 @@ impl DB {
             .client
             .database(&self.db_name)
             .collection::<BanPool>("banpools");
+        self.check_pool_access(pool_name, editor, false).await?;
 
         let ban = bans
-            .find_one(doc! {"user_id": user_id, "pool_name": pool_name}, None)
+            .find_one(
+                doc! {"user_id": &target.user_id, "pool_name": pool_name},
+                None,
+            )
             .await
             .unwrap();
 
         match ban {
-            Some(ban) => Err(anyhow!("Ban already exists")),
+            Some(_) => Err(anyhow!("Ban already exists")),
             None => {
                 let target_pool = banpools
                     .find_one(doc! {"pool_name": pool_name}, None)
@@ -193,12 +723,17 @@ impl DB {
                     .unwrap();
 
                 match target_pool {
-                    Some(pool) => {
+                    Some(_) => {
                         let new_ban = Ban {
-                            user_id: user_id.to_string(),
+                            user_id: target.user_id.clone(),
                             pool_name: pool_name.to_string(),
-                            creator_id: author_id.to_string(),
+                            creator_id: editor.user_id.clone(),
                             reason: reason.to_string(),
+                            expires_at,
+                            delete_message_days,
+                            username: target.username.clone(),
+                            discriminator: target.discriminator,
+                            avatar_url: target.avatar_url.clone(),
                             timestamp: Utc::now(),
                         };
                         bans.insert_one(new_ban, None).await.unwrap();
@@ -210,12 +745,18 @@ impl DB {
         }
     }
 
-    pub async fn delete_ban(&self, user_id: &str, pool_name: &str) -> Result<()> {
+    pub async fn delete_ban(
+        &self,
+        user_id: &str,
+        pool_name: &str,
+        editor: &PoolEditor,
+    ) -> Result<()> {
         // get the banpools collection
         let bans = self
             .client
             .database(&self.db_name)
             .collection::<Ban>("bans");
+        self.check_pool_access(pool_name, editor, false).await?;
 
         let ban = bans
             .delete_one(doc! {"pool_name": pool_name, "user_id": user_id}, None)
@@ -293,6 +834,77 @@ impl DB {
         Ok(bans)
     }
 
+    /// Inserts many bans into a pool at once, skipping users already in it.
+    /// Returns the IDs of the users that were added.
+    pub async fn add_bans(
+        &self,
+        pool_name: &str,
+        editor: &PoolEditor,
+        new_bans: Vec<Ban>,
+    ) -> Result<Vec<String>> {
+        let bans = self
+            .client
+            .database(&self.db_name)
+            .collection::<Ban>("bans");
+        self.check_pool_access(pool_name, editor, false).await?;
+
+        let mut existing: std::collections::HashSet<String> = self
+            .get_pool_bans(pool_name)
+            .await?
+            .into_iter()
+            .map(|ban| ban.user_id)
+            .collect();
+        // also drops duplicates within the batch itself
+        let new_bans: Vec<Ban> = new_bans
+            .into_iter()
+            .filter(|ban| ban.pool_name == pool_name && existing.insert(ban.user_id.clone()))
+            .collect();
+        if new_bans.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let user_ids = new_bans.iter().map(|ban| ban.user_id.clone()).collect();
+        bans.insert_many(new_bans, None).await?;
+        Ok(user_ids)
+    }
+
+    /// Finds one ban whose expiry has passed but which is still in its pool
+    pub async fn next_expired_ban(&self) -> Result<Option<Ban>> {
+        let bans = self
+            .client
+            .database(&self.db_name)
+            .collection::<Ban>("bans");
+
+        let ban = bans
+            .find_one(
+                doc! {"expires_at": {"$lte": bson::DateTime::from_chrono(Utc::now())}},
+                None,
+            )
+            .await?;
+
+        Ok(ban)
+    }
+
+    /// Removes an expired ban from its pool, unless it was renewed meanwhile
+    pub async fn remove_expired_ban(&self, ban: &Ban) -> Result<()> {
+        let bans = self
+            .client
+            .database(&self.db_name)
+            .collection::<Ban>("bans");
+
+        bans.delete_one(
+            doc! {
+                "user_id": &ban.user_id,
+                "pool_name": &ban.pool_name,
+                "expires_at": {"$lte": bson::DateTime::from_chrono(Utc::now())},
+            },
+            None,
+        )
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn add_exception(
         &self,
         user_id: &str,
@@ -376,10 +988,7 @@ impl DB {
             .await
             .unwrap();
 
-        match exception_query {
-            Some(_) => true,
-            None => false,
-        }
+        exception_query.is_some()
     }
 
     pub async fn add_subscription(
@@ -387,8 +996,8 @@ impl DB {
         pool_name: &str,
         server_id: &str,
         creator_id: &str,
-        subscription_level: &str,
-    ) -> Result<()> {
+        subscription_level: SubscriptionLevel,
+    ) -> Result<SubscriptionStatus> {
         let subscriptions = self
             .client
             .database(&self.db_name)
@@ -405,27 +1014,57 @@ impl DB {
             .unwrap();
 
         match subscription_query {
-            Some(_) => Err(anyhow!("Subscription already exists.")),
-            None => {
+            Some(subscription) if subscription.status == SubscriptionStatus::Pending => Err(
+                anyhow!("A subscription request is already waiting for the owner's approval."),
+            ),
+            // denied requests can be made again
+            Some(subscription) if subscription.status != SubscriptionStatus::Denied => {
+                Err(anyhow!("Subscription already exists."))
+            }
+            _ => {
                 let target_pool = banpools
                     .find_one(doc! {"pool_name": pool_name}, None)
                     .await
                     .unwrap();
 
                 match target_pool {
-                    Some(_) => {
+                    Some(pool) if !pool.can_subscribe(server_id) => Err(anyhow!(
+                        "{pool_name} is private, redeem an invite from its owner to subscribe"
+                    )),
+                    Some(pool) => {
+                        // owners don't need to approve their own subscriptions
+                        let status = if pool.requires_approval
+                            && pool.owner_id.as_deref() != Some(server_id)
+                        {
+                            SubscriptionStatus::Pending
+                        } else {
+                            SubscriptionStatus::Approved
+                        };
                         let new_subscription = Subscription {
                             pool_name: pool_name.to_string(),
                             server_id: server_id.to_string(),
-                            subscription_level: subscription_level.to_string(),
+                            subscription_level,
                             creator_id: creator_id.to_string(),
+                            status,
+                            approver_id: None,
+                            decided_at: None,
                             timestamp: Utc::now(),
                         };
+                        subscriptions
+                            .delete_one(
+                                doc! {
+                                    "pool_name": pool_name,
+                                    "server_id": server_id,
+                                    "status": "denied",
+                                },
+                                None,
+                            )
+                            .await?;
                         subscriptions
                             .insert_one(new_subscription, None)
                             .await
                             .unwrap();
-                        Ok(())
+                        Ok(status)
                     }
                     None => Err(anyhow!(
                         "Error adding subscription: This pool does not exist: {pool_name}"
@@ -475,6 +1114,80 @@ impl DB {
         Ok(sub_list)
     }
 
+    /// Lists the guild's subscriptions that are enforced
+    pub async fn list_active_subscriptions(&self, server_id: &str) -> Result<Vec<Subscription>> {
+        let subscriptions = self
+            .client
+            .database(&self.db_name)
+            .collection::<Subscription>("subscriptions");
+
+        let subscription_query = subscriptions
+            .find(active_subscriptions(doc! {"server_id": server_id}), None)
+            .await?;
+
+        let sub_list: Vec<Subscription> = subscription_query.try_collect().await?;
+
+        Ok(sub_list)
+    }
+
+    /// Fetches the guild's subscription to the pool if it is enforced
+    pub async fn get_subscription(&self, pool_name: &str, server_id: &str) -> Result<Subscription> {
+        let subscriptions = self
+            .client
+            .database(&self.db_name)
+            .collection::<Subscription>("subscriptions");
+
+        let subscription = subscriptions
+            .find_one(
+                active_subscriptions(doc! {"pool_name": pool_name, "server_id": server_id}),
+                None,
+            )
+            .await?;
+
+        match subscription {
+            Some(subscription) => Ok(subscription),
+            None => Err(anyhow!("This server is not subscribed to {pool_name}")),
+        }
+    }
+
+    /// Records the owner's decision on a pending subscription
+    pub async fn decide_subscription(
+        &self,
+        pool_name: &str,
+        server_id: &str,
+        status: SubscriptionStatus,
+        approver_id: &str,
+    ) -> Result<()> {
+        let subscriptions = self
+            .client
+            .database(&self.db_name)
+            .collection::<Subscription>("subscriptions");
+
+        info!(
+            "Setting subscription of {} to {} to {:?}",
+            server_id, pool_name, status
+        );
+        let result = subscriptions
+            .update_one(
+                doc! {"pool_name": pool_name, "server_id": server_id, "status": "pending"},
+                doc! {"$set": {
+                    "status": bson::to_bson(&status)?,
+                    "approver_id": approver_id,
+                    "decided_at": bson::DateTime::from_chrono(Utc::now()),
+                }},
+                None,
+            )
+            .await?;
+
+        if result.modified_count > 0 {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "This subscription is no longer waiting for approval"
+            ))
+        }
+    }
+
     pub async fn list_subscribed_servers(&self, pool_name: &str) -> Result<Vec<Subscription>> {
         let subscriptions = self
             .client
@@ -482,7 +1195,7 @@ impl DB {
             .collection::<Subscription>("subscriptions");
 
         let subscription_query = subscriptions
-            .find(doc! {"pool_name": pool_name}, None)
+            .find(active_subscriptions(doc! {"pool_name": pool_name}), None)
             .await
             .unwrap();
 
@@ -491,6 +1204,21 @@ impl DB {
         Ok(sub_list)
     }
 
+    pub async fn list_all_subscriptions(&self) -> Result<Vec<Subscription>> {
+        let subscriptions = self
+            .client
+            .database(&self.db_name)
+            .collection::<Subscription>("subscriptions");
+
+        let subscription_query = subscriptions
+            .find(active_subscriptions(doc! {}), None)
+            .await?;
+
+        let sub_list: Vec<Subscription> = subscription_query.try_collect().await?;
+
+        Ok(sub_list)
+    }
+
     pub async fn add_notification_channel(
         &self,
         server_id: &str,
@@ -507,26 +1235,28 @@ impl DB {
             .await
             .unwrap();
 
+        info!(
+            "Setting {} as notification Channel for {}",
+            channel_id, server_id
+        );
+        let new_notification = NotificationChannel {
+            server_id: server_id.to_string(),
+            channel_id: channel_id.to_string(),
+            author_id: author_id.to_string(),
+            timestamp: Utc::now(),
+        };
+        // setting a channel again moves notifications to the new one
         match notification {
-            Some(_) => Err(anyhow!("Notification channel already set")),
+            Some(_) => {
+                notifications
+                    .replace_one(doc! {"server_id": server_id}, new_notification, None)
+                    .await?;
+            }
             None => {
-                info!(
-                    "Setting {} as notification Channel for {}",
-                    channel_id, server_id
-                );
-                let new_notification = NotificationChannel {
-                    server_id: server_id.to_string(),
-                    channel_id: channel_id.to_string(),
-                    author_id: author_id.to_string(),
-                    timestamp: Utc::now(),
-                };
-                let insert_result = notifications
-                    .insert_one(new_notification, None)
-                    .await
-                    .unwrap();
-                Ok(())
+                notifications.insert_one(new_notification, None).await?;
             }
         }
+        Ok(())
     }
 
     pub async fn delete_notification_channel(&self, server_id: &str) -> Result<()> {
@@ -584,20 +1314,25 @@ impl DB {
             .await
             .unwrap();
 
+        info!("Setting {} as Admin role for {}", role_id, server_id);
+        let new_role = AdminRole {
+            server_id: server_id.to_string(),
+            role_id: role_id.to_string(),
+            author_id: author_id.to_string(),
+            timestamp: Utc::now(),
+        };
+        // setting a role again replaces the previous one
         match admin_role {
-            Some(_) => Err(anyhow!("Admin role already set")),
+            Some(_) => {
+                admin_roles
+                    .replace_one(doc! {"server_id": server_id}, new_role, None)
+                    .await?;
+            }
             None => {
-                info!("Setting {} as Admin role for {}", role_id, server_id);
-                let new_role = AdminRole {
-                    server_id: server_id.to_string(),
-                    role_id: role_id.to_string(),
-                    author_id: author_id.to_string(),
-                    timestamp: Utc::now(),
-                };
-                let insert_result = admin_roles.insert_one(new_role, None).await.unwrap();
-                Ok(())
+                admin_roles.insert_one(new_role, None).await?;
             }
         }
+        Ok(())
     }
 
     pub async fn delete_admin_role(&self, server_id: &str) -> Result<()> {
@@ -635,4 +1370,516 @@ impl DB {
             None => Err(anyhow!("No admin role has been set")),
         }
     }
+
+    pub async fn add_action(
+        &self,
+        server_id: &str,
+        user_id: &str,
+        pool_name: &str,
+        action: ActionKind,
+        error: Option<String>,
+        expires_at: Option<chrono::DateTime<Utc>>,
+    ) -> Result<()> {
+        let actions = self
+            .client
+            .database(&self.db_name)
+            .collection::<EnforcementAction>("actions");
+
+        let new_action = EnforcementAction {
+            server_id: server_id.to_string(),
+            user_id: user_id.to_string(),
+            pool_name: pool_name.to_string(),
+            action,
+            result: match error {
+                Some(_) => ActionResult::Failure,
+                None => ActionResult::Success,
+            },
+            error,
+            expires_at,
+            timestamp: Utc::now(),
+        };
+        actions.insert_one(new_action, None).await?;
+        Ok(())
+    }
+
+    pub async fn get_user_actions(&self, user_id: &str) -> Result<Vec<EnforcementAction>> {
+        let actions = self
+            .client
+            .database(&self.db_name)
+            .collection::<EnforcementAction>("actions");
+
+        let options = FindOptions::builder().sort(doc! {"timestamp": -1}).build();
+        let action_query = actions.find(doc! {"user_id": user_id}, options).await?;
+
+        let actions: Vec<EnforcementAction> = action_query.try_collect().await?;
+
+        Ok(actions)
+    }
+
+    /// Returns the most recent successful action of one of the given kinds
+    /// against a user in a guild that was made because of the given pool
+    pub async fn last_action(
+        &self,
+        server_id: &str,
+        user_id: &str,
+        pool_name: &str,
+        kinds: &[ActionKind],
+    ) -> Result<Option<EnforcementAction>> {
+        let actions = self
+            .client
+            .database(&self.db_name)
+            .collection::<EnforcementAction>("actions");
+
+        let options = FindOneOptions::builder()
+            .sort(doc! {"timestamp": -1})
+            .build();
+        let action = actions
+            .find_one(
+                doc! {
+                    "server_id": server_id,
+                    "user_id": user_id,
+                    "pool_name": pool_name,
+                    "action": {"$in": bson::to_bson(kinds)?},
+                    "result": "success",
+                },
+                options,
+            )
+            .await?;
+
+        Ok(action)
+    }
+
+    pub async fn get_server_config(&self, server_id: &str) -> Result<Option<ServerConfig>> {
+        let configs = self
+            .client
+            .database(&self.db_name)
+            .collection::<ServerConfig>("serverconfigs");
+
+        let config = configs
+            .find_one(doc! {"server_id": server_id}, None)
+            .await?;
+
+        Ok(config)
+    }
+
+    pub async fn is_shadow_mode(&self, server_id: &str) -> bool {
+        match self.get_server_config(server_id).await {
+            Ok(Some(config)) => config.shadow_mode,
+            _ => false,
+        }
+    }
+
+    pub async fn set_shadow_mode(
+        &self,
+        server_id: &str,
+        shadow_mode: bool,
+        author_id: &str,
+    ) -> Result<()> {
+        info!("Setting shadow mode to {} for {}", shadow_mode, server_id);
+        self.set_server_config(server_id, author_id, doc! {"shadow_mode": shadow_mode})
+            .await
+    }
+
+    pub async fn set_timeout_minutes(
+        &self,
+        server_id: &str,
+        timeout_minutes: i64,
+        author_id: &str,
+    ) -> Result<()> {
+        info!(
+            "Setting timeout to {} minutes for {}",
+            timeout_minutes, server_id
+        );
+        self.set_server_config(
+            server_id,
+            author_id,
+            doc! {"timeout_minutes": timeout_minutes},
+        )
+        .await
+    }
+
+    pub async fn set_reconcile_repair(
+        &self,
+        server_id: &str,
+        reconcile_repair: bool,
+        author_id: &str,
+    ) -> Result<()> {
+        info!(
+            "Setting reconcile repair to {} for {}",
+            reconcile_repair, server_id
+        );
+        self.set_server_config(
+            server_id,
+            author_id,
+            doc! {"reconcile_repair": reconcile_repair},
+        )
+        .await
+    }
+
+    pub async fn set_account_age(
+        &self,
+        server_id: &str,
+        min_account_age_days: Option<i64>,
+        account_age_action: SubscriptionLevel,
+        author_id: &str,
+    ) -> Result<()> {
+        info!(
+            "Setting minimum account age to {:?} days ({:?}) for {}",
+            min_account_age_days, account_age_action, server_id
+        );
+        self.set_server_config(
+            server_id,
+            author_id,
+            doc! {
+                "min_account_age_days": min_account_age_days,
+                "account_age_action": bson::to_bson(&account_age_action)?,
+            },
+        )
+        .await
+    }
+
+    pub async fn set_delete_message_days(
+        &self,
+        server_id: &str,
+        delete_message_days: u8,
+        author_id: &str,
+    ) -> Result<()> {
+        info!(
+            "Setting message deletion to {} days for {}",
+            delete_message_days, server_id
+        );
+        self.set_server_config(
+            server_id,
+            author_id,
+            doc! {"delete_message_days": i32::from(delete_message_days)},
+        )
+        .await
+    }
+
+    /// Updates the given fields of a server's config, creating it if needed
+    async fn set_server_config(
+        &self,
+        server_id: &str,
+        author_id: &str,
+        mut fields: Document,
+    ) -> Result<()> {
+        let configs = self
+            .client
+            .database(&self.db_name)
+            .collection::<ServerConfig>("serverconfigs");
+
+        fields.insert("author_id", author_id);
+        fields.insert("timestamp", bson::DateTime::from_chrono(Utc::now()));
+        let options = UpdateOptions::builder().upsert(true).build();
+        configs
+            .update_one(
+                doc! {"server_id": server_id},
+                doc! {"$set": fields},
+                options,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn add_jobs(&self, jobs: Vec<EnforcementJob>) -> Result<()> {
+        if jobs.is_empty() {
+            return Ok(());
+        }
+
+        let queue = self
+            .client
+            .database(&self.db_name)
+            .collection::<EnforcementJob>("jobs");
+
+        queue.insert_many(jobs, None).await?;
+        Ok(())
+    }
+
+    /// Whether a job for this user, pool and guild is still waiting or running
+    pub async fn has_open_job(
+        &self,
+        kind: JobKind,
+        server_id: &str,
+        user_id: &str,
+        pool_name: &str,
+    ) -> Result<bool> {
+        let queue = self
+            .client
+            .database(&self.db_name)
+            .collection::<EnforcementJob>("jobs");
+
+        let job = queue
+            .find_one(
+                doc! {
+                    "kind": bson::to_bson(&kind)?,
+                    "server_id": server_id,
+                    "user_id": user_id,
+                    "pool_name": pool_name,
+                    "status": {"$in": ["pending", "running"]},
+                },
+                None,
+            )
+            .await?;
+        Ok(job.is_some())
+    }
+
+    /// Whether an identical job has failed for good since the given time.
+    /// Failed jobs keep the time of their last attempt in `next_attempt`.
+    pub async fn has_failed_job(
+        &self,
+        kind: JobKind,
+        server_id: &str,
+        user_id: &str,
+        pool_name: &str,
+        since: chrono::DateTime<Utc>,
+    ) -> Result<bool> {
+        let queue = self
+            .client
+            .database(&self.db_name)
+            .collection::<EnforcementJob>("jobs");
+
+        let job = queue
+            .find_one(
+                doc! {
+                    "kind": bson::to_bson(&kind)?,
+                    "server_id": server_id,
+                    "user_id": user_id,
+                    "pool_name": pool_name,
+                    "status": "failed",
+                    "next_attempt": {"$gte": bson::DateTime::from_chrono(since)},
+                },
+                None,
+            )
+            .await?;
+        Ok(job.is_some())
+    }
+
+    /// Marks the next due job as running and returns it
+    pub async fn claim_job(&self) -> Result<Option<EnforcementJob>> {
+        let queue = self
+            .client
+            .database(&self.db_name)
+            .collection::<EnforcementJob>("jobs");
+
+        let options = FindOneAndUpdateOptions::builder()
+            .sort(doc! {"next_attempt": 1})
+            .return_document(ReturnDocument::After)
+            .build();
+        let job = queue
+            .find_one_and_update(
+                doc! {
+                    "status": "pending",
+                    "next_attempt": {"$lte": bson::DateTime::from_chrono(Utc::now())},
+                },
+                doc! {
+                    "$set": {"status": "running"},
+                    "$inc": {"attempts": 1},
+                },
+                options,
+            )
+            .await?;
+
+        Ok(job)
+    }
+
+    pub async fn complete_job(&self, id: ObjectId) -> Result<()> {
+        let queue = self
+            .client
+            .database(&self.db_name)
+            .collection::<EnforcementJob>("jobs");
+
+        queue.delete_one(doc! {"_id": id}, None).await?;
+        Ok(())
+    }
+
+    pub async fn retry_job(
+        &self,
+        id: ObjectId,
+        next_attempt: chrono::DateTime<Utc>,
+        error: &str,
+    ) -> Result<()> {
+        let queue = self
+            .client
+            .database(&self.db_name)
+            .collection::<EnforcementJob>("jobs");
+
+        queue
+            .update_one(
+                doc! {"_id": id},
+                doc! {"$set": {
+                    "status": "pending",
+                    "last_error": error,
+                    "next_attempt": bson::DateTime::from_chrono(next_attempt),
+                }},
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn fail_job(&self, id: ObjectId, error: &str) -> Result<()> {
+        let queue = self
+            .client
+            .database(&self.db_name)
+            .collection::<EnforcementJob>("jobs");
+
+        queue
+            .update_one(
+                doc! {"_id": id},
+                doc! {"$set": {"status": "failed", "last_error": error}},
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Puts jobs that were running when the bot stopped back in the queue
+    pub async fn requeue_running_jobs(&self) -> Result<u64> {
+        let queue = self
+            .client
+            .database(&self.db_name)
+            .collection::<EnforcementJob>("jobs");
+
+        let result = queue
+            .update_many(
+                doc! {"status": "running"},
+                doc! {"$set": {"status": "pending"}},
+                None,
+            )
+            .await?;
+        Ok(result.modified_count)
+    }
+
+    pub async fn count_jobs(&self, status: JobStatus) -> Result<u64> {
+        let queue = self
+            .client
+            .database(&self.db_name)
+            .collection::<EnforcementJob>("jobs");
+
+        let count = queue
+            .count_documents(doc! {"status": bson::to_bson(&status)?}, None)
+            .await?;
+        Ok(count)
+    }
+
+    pub async fn add_quarantine_role(
+        &self,
+        server_id: &str,
+        role_id: &str,
+        author_id: &str,
+    ) -> Result<()> {
+        let quarantine_roles = self
+            .client
+            .database(&self.db_name)
+            .collection::<QuarantineRole>("quarantineroles");
+
+        let quarantine_role = quarantine_roles
+            .find_one(doc! {"server_id": server_id}, None)
+            .await?;
+
+        info!("Setting {} as Quarantine role for {}", role_id, server_id);
+        let new_role = QuarantineRole {
+            server_id: server_id.to_string(),
+            role_id: role_id.to_string(),
+            author_id: author_id.to_string(),
+            timestamp: Utc::now(),
+        };
+        // setting a role again replaces the previous one
+        match quarantine_role {
+            Some(_) => {
+                quarantine_roles
+                    .replace_one(doc! {"server_id": server_id}, new_role, None)
+                    .await?;
+            }
+            None => {
+                quarantine_roles.insert_one(new_role, None).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn delete_quarantine_role(&self, server_id: &str) -> Result<()> {
+        let quarantine_roles = self
+            .client
+            .database(&self.db_name)
+            .collection::<QuarantineRole>("quarantineroles");
+
+        let quarantine_role = quarantine_roles
+            .delete_one(doc! {"server_id": server_id}, None)
+            .await?;
+
+        if quarantine_role.deleted_count > 0 {
+            Ok(())
+        } else {
+            Err(anyhow!("Unable to remove quarantine role. No role is set"))
+        }
+    }
+
+    pub async fn list_quarantine_role(&self, server_id: &str) -> Result<QuarantineRole> {
+        let quarantine_roles = self
+            .client
+            .database(&self.db_name)
+            .collection::<QuarantineRole>("quarantineroles");
+
+        let quarantine_role = quarantine_roles
+            .find_one(doc! {"server_id": server_id}, None)
+            .await?;
+
+        match quarantine_role {
+            Some(role) => Ok(role),
+            None => Err(anyhow!("No quarantine role has been set")),
+        }
+    }
+
+    pub async fn add_quarantined_member(
+        &self,
+        server_id: &str,
+        user_id: &str,
+        pool_name: &str,
+        removed_roles: Vec<String>,
+    ) -> Result<()> {
+        let quarantines = self
+            .client
+            .database(&self.db_name)
+            .collection::<QuarantinedMember>("quarantines");
+
+        let new_quarantine = QuarantinedMember {
+            server_id: server_id.to_string(),
+            user_id: user_id.to_string(),
+            pool_name: pool_name.to_string(),
+            removed_roles,
+            timestamp: Utc::now(),
+        };
+        quarantines.insert_one(new_quarantine, None).await?;
+        Ok(())
+    }
+
+    pub async fn get_quarantined_member(
+        &self,
+        server_id: &str,
+        user_id: &str,
+    ) -> Result<Option<QuarantinedMember>> {
+        let quarantines = self
+            .client
+            .database(&self.db_name)
+            .collection::<QuarantinedMember>("quarantines");
+
+        let quarantine = quarantines
+            .find_one(doc! {"server_id": server_id, "user_id": user_id}, None)
+            .await?;
+
+        Ok(quarantine)
+    }
+
+    pub async fn delete_quarantined_member(&self, server_id: &str, user_id: &str) -> Result<()> {
+        let quarantines = self
+            .client
+            .database(&self.db_name)
+            .collection::<QuarantinedMember>("quarantines");
+
+        quarantines
+            .delete_one(doc! {"server_id": server_id, "user_id": user_id}, None)
+            .await?;
+        Ok(())
+    }
 }
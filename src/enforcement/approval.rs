@@ -0,0 +1,136 @@
+use super::{notification_channel, notify};
+use crate::commands::checks::has_admin_access;
+use crate::db::mongo::{SubscriptionLevel, SubscriptionStatus, DB};
+use anyhow::{anyhow, Result};
+use log::info;
+use poise::serenity_prelude as serenity;
+
+// Custom ID prefixes of the buttons on an approval request, followed by the
+// subscribing guild and the pool
+const APPROVE_PREFIX: &str = "approve-sub:";
+const DENY_PREFIX: &str = "deny-sub:";
+
+/// Asks the guild that owns the pool to approve a new subscription
+pub async fn request_approval(
+    http: &serenity::Http,
+    db: &DB,
+    server_id: &str,
+    pool_name: &str,
+    level: SubscriptionLevel,
+    creator_id: &str,
+) -> Result<()> {
+    let owner_id = db
+        .get_pool(pool_name)
+        .await?
+        .owner_id
+        .ok_or_else(|| anyhow!("{pool_name} has no owner to approve subscriptions"))?;
+    let guild_name = serenity::GuildId(server_id.parse::<u64>()?)
+        .to_partial_guild(http)
+        .await
+        .map(|guild| guild.name)
+        .unwrap_or_else(|_| server_id.to_string());
+
+    notification_channel(db, &owner_id)
+        .await?
+        .send_message(http, |m| {
+            m.embed(|e| {
+                e.title("Subscription Request")
+                    .color(serenity::Color::ORANGE)
+                    .description(format!(
+                        "{guild_name} (`{server_id}`) wants to subscribe to `{pool_name}` with level {level:?}. Requested by <@{creator_id}>."
+                    ))
+            })
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|b| {
+                        b.style(serenity::ButtonStyle::Success)
+                            .label("Approve")
+                            .custom_id(format!("{APPROVE_PREFIX}{server_id}:{pool_name}"))
+                    })
+                    .create_button(|b| {
+                        b.style(serenity::ButtonStyle::Danger)
+                            .label("Deny")
+                            .custom_id(format!("{DENY_PREFIX}{server_id}:{pool_name}"))
+                    })
+                })
+            })
+        })
+        .await?;
+    info!(
+        "Asked {} to approve {} subscribing to {}",
+        owner_id, server_id, pool_name
+    );
+    Ok(())
+}
+
+/// Handles the buttons of an approval request, ignoring interactions that
+/// belong to something else
+pub async fn handle_interaction(
+    http: &serenity::Http,
+    db: &DB,
+    interaction: &serenity::MessageComponentInteraction,
+) -> Result<()> {
+    let custom_id = &interaction.data.custom_id;
+    let (status, ids) = if let Some(ids) = custom_id.strip_prefix(APPROVE_PREFIX) {
+        (SubscriptionStatus::Approved, ids)
+    } else if let Some(ids) = custom_id.strip_prefix(DENY_PREFIX) {
+        (SubscriptionStatus::Denied, ids)
+    } else {
+        return Ok(());
+    };
+    let (server_id, pool_name) = ids
+        .split_once(':')
+        .ok_or_else(|| anyhow!("Malformed approval button `{custom_id}`"))?;
+
+    // only admins of the owning guild get to decide
+    let owner_id = db.get_pool(pool_name).await?.owner_id;
+    let allowed = match &interaction.member {
+        Some(member) => {
+            owner_id.as_deref() == Some(&member.guild_id.to_string())
+                && has_admin_access(db, member).await
+        }
+        None => false,
+    };
+    if !allowed {
+        interaction
+            .create_interaction_response(http, |r| {
+                r.interaction_response_data(|d| {
+                    d.ephemeral(true).content(format!(
+                        "You need this server's admin role to approve subscriptions to {pool_name}"
+                    ))
+                })
+            })
+            .await?;
+        return Ok(());
+    }
+
+    let approver_id = interaction.user.id;
+    db.decide_subscription(pool_name, server_id, status, &approver_id.to_string())
+        .await?;
+    let decision = match status {
+        SubscriptionStatus::Approved => "approved",
+        _ => "denied",
+    };
+    info!(
+        "{} {} the subscription of {} to {}",
+        approver_id, decision, server_id, pool_name
+    );
+
+    interaction
+        .create_interaction_response(http, |r| {
+            r.kind(serenity::InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.content(format!("<@{approver_id}> {decision} this subscription"))
+                        .components(|c| c)
+                })
+        })
+        .await?;
+
+    let description = match status {
+        SubscriptionStatus::Approved => format!(
+            "The subscription to `{pool_name}` was approved and is now enforced. Use `/subscription sweep` to check existing members."
+        ),
+        _ => format!("The subscription to `{pool_name}` was denied by its owner."),
+    };
+    notify(http, db, server_id, "Subscription Request", &description).await
+}
@@ -0,0 +1,47 @@
+use super::queue::enqueue_pool;
+use crate::db::mongo::{JobKind, DB};
+use log::{error, info};
+use std::time::Duration;
+
+// How often the pools are checked for expired temporary bans
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Removes temporary bans from their pools once they expire and queues lifting
+/// them in every subscribed guild, for as long as the bot is running
+pub async fn run_expiry() {
+    let db = match DB::init().await {
+        Ok(db) => db,
+        Err(e) => {
+            error!("Ban expiry could not connect to the database: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        match db.next_expired_ban().await {
+            Ok(Some(ban)) => {
+                info!("Ban of {} from {} expired", ban.user_id, ban.pool_name);
+                // the ban stays in the pool until lifting it is queued, so a
+                // failure here is retried on the next check instead of lost
+                let queued =
+                    enqueue_pool(&db, JobKind::Lift, &ban.user_id, &ban.pool_name, "").await;
+                let result = match queued {
+                    Ok(_) => db.remove_expired_ban(&ban).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    error!(
+                        "Unable to lift the expired ban of {} from {}: {}",
+                        ban.user_id, ban.pool_name, e
+                    );
+                    tokio::time::sleep(EXPIRY_INTERVAL).await;
+                }
+            }
+            Ok(None) => tokio::time::sleep(EXPIRY_INTERVAL).await,
+            Err(e) => {
+                error!("Unable to check for expired bans: {}", e);
+                tokio::time::sleep(EXPIRY_INTERVAL).await;
+            }
+        }
+    }
+}
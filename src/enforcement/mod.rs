@@ -0,0 +1,1120 @@
+use crate::db::mongo::{
+    ActionKind, Ban, JobKind, PoolEditor, QuarantinedMember, Subscription, SubscriptionLevel,
+    ACCOUNT_AGE_GATE, DB,
+};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use log::{error, info};
+use once_cell::sync::Lazy;
+use poise::serenity_prelude as serenity;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::fmt;
+
+pub mod approval;
+pub mod expiry;
+pub mod queue;
+pub mod reconcile;
+pub mod submission;
+
+// Discord rejects audit log reasons longer than this
+const MAX_AUDIT_REASON: usize = 512;
+// Start of the audit log reason on every ban issued on behalf of a pool
+const POOL_AUDIT_PREFIX: &str = "Banpool ";
+// Discord's maximum page size when listing guild members
+const MEMBER_PAGE_SIZE: u64 = 1000;
+// Discord's maximum page size when listing guild bans
+const BAN_PAGE_SIZE: usize = 1000;
+// Base of the Discord API routes serenity can't build itself
+const DISCORD_API: &str = "https://discord.com/api/v10";
+// Used when a guild with timeout subscriptions hasn't configured a duration
+const DEFAULT_TIMEOUT_MINUTES: i64 = 60 * 24;
+// Keeps reports comfortably under the 4096 character embed description limit
+const MAX_REPORT_LENGTH: usize = 3900;
+// Start of the audit log reason on every action of the account age gate. No
+// pool's reason can start with it.
+const ACCOUNT_AGE_AUDIT_PREFIX: &str = "Account age gate: ";
+
+/// What happened in a single subscribed guild when a pool ban was enforced
+#[derive(Debug)]
+pub enum Outcome {
+    Banned,
+    Kicked,
+    Notified,
+    Unbanned,
+    Quarantined,
+    Released,
+    TimedOut(DateTime<Utc>),
+    Shadowed(ActionKind),
+    Skipped(String),
+    Failed(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Banned => write!(f, "banned"),
+            Outcome::Kicked => write!(f, "kicked"),
+            Outcome::Notified => write!(f, "notified"),
+            Outcome::Unbanned => write!(f, "unbanned"),
+            Outcome::Quarantined => write!(f, "quarantined"),
+            Outcome::Released => write!(f, "released from quarantine"),
+            Outcome::TimedOut(until) => write!(f, "timed out until {until}"),
+            Outcome::Shadowed(kind) => write!(f, "shadow mode, would {kind:?}"),
+            Outcome::Skipped(why) => write!(f, "skipped ({why})"),
+            Outcome::Failed(e) => write!(f, "failed ({e})"),
+        }
+    }
+}
+
+/// A guild setting that enforcement needs is missing or invalid. Retrying
+/// won't help until the guild's admins fix it.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct NotConfigured(String);
+
+/// Prefix of the audit log reason on every ban issued on behalf of a pool, used
+/// to tell those bans apart from ones a guild issued itself
+fn audit_prefix(pool_name: &str) -> String {
+    format!("{POOL_AUDIT_PREFIX}{pool_name}: ")
+}
+
+/// Whether a guild ban was issued by the bot on behalf of any pool or the
+/// account age gate, rather than by the guild's own moderators
+pub fn issued_by_bot(ban: &serenity::Ban) -> bool {
+    ban.reason.as_deref().is_some_and(|reason| {
+        reason.starts_with(POOL_AUDIT_PREFIX) || reason.starts_with(ACCOUNT_AGE_AUDIT_PREFIX)
+    })
+}
+
+/// Builds the audit log reason attached to bans issued on behalf of a pool
+pub fn audit_reason(pool_name: &str, reason: &str) -> String {
+    truncate_audit_reason(format!("{}{reason}", audit_prefix(pool_name)))
+}
+
+fn truncate_audit_reason(reason: String) -> String {
+    reason.chars().take(MAX_AUDIT_REASON).collect()
+}
+
+/// What the bot acts on behalf of when it enforces something in a guild
+#[derive(Debug, Clone, Copy)]
+enum Cause<'a> {
+    Pool(&'a str),
+    AccountAge,
+}
+
+impl Cause<'_> {
+    /// The name the action is recorded under in the action log
+    fn name(&self) -> &str {
+        match self {
+            Cause::Pool(pool_name) => pool_name,
+            Cause::AccountAge => ACCOUNT_AGE_GATE,
+        }
+    }
+
+    fn audit_reason(&self, reason: &str) -> String {
+        match self {
+            Cause::Pool(pool_name) => audit_reason(pool_name, reason),
+            Cause::AccountAge => {
+                truncate_audit_reason(format!("{ACCOUNT_AGE_AUDIT_PREFIX}{reason}"))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Cause<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cause::Pool(pool_name) => write!(f, "the `{pool_name}` pool"),
+            Cause::AccountAge => write!(f, "the account age gate"),
+        }
+    }
+}
+
+/// A level to apply to a user in a guild and what it is applied for
+struct Enforcement<'a> {
+    server_id: &'a str,
+    level: SubscriptionLevel,
+    cause: Cause<'a>,
+    delete_message_days: Option<u8>,
+}
+
+/// The HTTP status Discord answered with, if the error came from the API
+pub fn status_code(e: &anyhow::Error) -> Option<serenity::StatusCode> {
+    match e.downcast_ref::<serenity::Error>() {
+        Some(serenity::Error::Http(http)) => http.status_code(),
+        _ => None,
+    }
+}
+
+/// Whether Discord refused the request because the target is unknown, e.g.
+/// kicking a user that is not a member of the guild
+fn is_not_found(e: &anyhow::Error) -> bool {
+    status_code(e) == Some(serenity::StatusCode::NOT_FOUND)
+}
+
+/// Lists users one per line, cutting the list short once it reaches `budget`
+/// characters
+pub fn list_users(user_ids: &[String], budget: usize) -> String {
+    let mut list = String::new();
+    for (shown, user_id) in user_ids.iter().enumerate() {
+        let line = format!("<@{user_id}> (`{user_id}`)\n");
+        if list.len() + line.len() > budget {
+            list.push_str(&format!("...and {} more\n", user_ids.len() - shown));
+            break;
+        }
+        list.push_str(&line);
+    }
+    list
+}
+
+static API_CLIENT: Lazy<reqwest::Client> = Lazy::new(reqwest::Client::new);
+
+/// Sends a GET request for a Discord route serenity 0.11 has no method for,
+/// handing error responses back as serenity errors
+async fn get_api<T: DeserializeOwned>(http: &serenity::Http, path: &str) -> Result<T> {
+    let response = API_CLIENT
+        .get(format!("{DISCORD_API}{path}"))
+        .header(reqwest::header::AUTHORIZATION, &http.token)
+        .header(reqwest::header::USER_AGENT, serenity::constants::USER_AGENT)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let e = serenity::HttpError::from_response(response).await;
+        return Err(serenity::Error::Http(Box::new(e)).into());
+    }
+    Ok(response.json().await?)
+}
+
+/// Lists every ban in a guild. Serenity's `GuildId::bans` only returns the
+/// first page, so the list is paged through here.
+pub async fn guild_bans(
+    http: &serenity::Http,
+    guild_id: serenity::GuildId,
+) -> Result<Vec<serenity::Ban>> {
+    let mut bans: Vec<serenity::Ban> = Vec::new();
+    loop {
+        let mut path = format!("/guilds/{guild_id}/bans?limit={BAN_PAGE_SIZE}");
+        if let Some(last) = bans.last() {
+            path.push_str(&format!("&after={}", last.user.id));
+        }
+        let page: Vec<serenity::Ban> = get_api(http, &path).await?;
+        let done = page.len() < BAN_PAGE_SIZE;
+        bans.extend(page);
+        if done {
+            return Ok(bans);
+        }
+    }
+}
+
+/// The user's ban in a guild, if they are banned there
+async fn guild_ban(
+    http: &serenity::Http,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+) -> Result<Option<serenity::Ban>> {
+    match get_api(http, &format!("/guilds/{guild_id}/bans/{user_id}")).await {
+        Ok(ban) => Ok(Some(ban)),
+        Err(e) if is_not_found(&e) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The guild's notification channel, if one has been set
+async fn notification_channel(db: &DB, server_id: &str) -> Result<serenity::ChannelId> {
+    let channel = db
+        .list_notification_channel(server_id)
+        .await
+        .map_err(|e| NotConfigured(e.to_string()))?;
+    let channel_id = channel.channel_id.parse::<u64>().map_err(|_| {
+        NotConfigured(format!(
+            "Invalid notification channel ID `{}`",
+            channel.channel_id
+        ))
+    })?;
+    Ok(serenity::ChannelId(channel_id))
+}
+
+/// Posts an embed to the guild's notification channel, if one has been set
+pub async fn notify(
+    http: &serenity::Http,
+    db: &DB,
+    server_id: &str,
+    title: &str,
+    description: &str,
+) -> Result<()> {
+    notification_channel(db, server_id)
+        .await?
+        .send_message(http, |m| {
+            m.embed(|e| {
+                e.title(title)
+                    .color(serenity::Color::ORANGE)
+                    .description(description)
+            })
+        })
+        .await?;
+    Ok(())
+}
+
+/// Applies the subscription's level to a pool member in the subscribing guild
+pub async fn apply_level(
+    http: &serenity::Http,
+    db: &DB,
+    subscription: &Subscription,
+    user_id: serenity::UserId,
+    reason: &str,
+    delete_message_days: Option<u8>,
+) -> Outcome {
+    enforce(http, db, subscription, user_id, reason, delete_message_days)
+        .await
+        .unwrap_or_else(|e| Outcome::Failed(e.to_string()))
+}
+
+/// Applies the subscription's level, recording the attempt in the action log
+/// and handing Discord errors back to the caller
+async fn enforce(
+    http: &serenity::Http,
+    db: &DB,
+    subscription: &Subscription,
+    user_id: serenity::UserId,
+    reason: &str,
+    delete_message_days: Option<u8>,
+) -> Result<Outcome> {
+    let enforcement = Enforcement {
+        server_id: &subscription.server_id,
+        level: subscription.subscription_level,
+        cause: Cause::Pool(&subscription.pool_name),
+        delete_message_days,
+    };
+    act(http, db, &enforcement, user_id, reason).await
+}
+
+/// Applies a level to a user in a guild, recording the attempt in the action
+/// log and handing Discord errors back to the caller
+async fn act(
+    http: &serenity::Http,
+    db: &DB,
+    enforcement: &Enforcement<'_>,
+    user_id: serenity::UserId,
+    reason: &str,
+) -> Result<Outcome> {
+    let server_id = enforcement.server_id;
+    let cause = enforcement.cause;
+    let guild_id = serenity::GuildId(server_id.parse::<u64>()?);
+    let audit_reason = cause.audit_reason(reason);
+
+    if enforcement.level != SubscriptionLevel::NotifyOnly && db.is_shadow_mode(server_id).await {
+        let kind = match enforcement.level {
+            SubscriptionLevel::AutoKick => ActionKind::Kick,
+            SubscriptionLevel::Quarantine => ActionKind::Quarantine,
+            SubscriptionLevel::Timeout => ActionKind::Timeout,
+            _ => ActionKind::Ban,
+        };
+        return Ok(shadow(http, db, server_id, user_id, cause, kind, reason).await);
+    }
+
+    let (kind, result): (ActionKind, Result<Outcome>) = match enforcement.level {
+        SubscriptionLevel::AutoBan => (
+            ActionKind::Ban,
+            ban(
+                http,
+                db,
+                guild_id,
+                user_id,
+                enforcement.delete_message_days,
+                &audit_reason,
+            )
+            .await,
+        ),
+        SubscriptionLevel::AutoKick => (
+            ActionKind::Kick,
+            guild_id
+                .kick_with_reason(http, user_id, &audit_reason)
+                .await
+                .map(|_| Outcome::Kicked)
+                .map_err(Into::into),
+        ),
+        SubscriptionLevel::Quarantine => (
+            ActionKind::Quarantine,
+            quarantine(http, db, guild_id, user_id, cause.name()).await,
+        ),
+        SubscriptionLevel::Timeout => (
+            ActionKind::Timeout,
+            timeout(http, db, guild_id, user_id).await,
+        ),
+        SubscriptionLevel::NotifyOnly => {
+            let flagged = match cause {
+                Cause::Pool(pool_name) => format!("is in the `{pool_name}` pool"),
+                Cause::AccountAge => String::from("has a new account"),
+            };
+            let description = format!("<@{user_id}> (`{user_id}`) {flagged}.\nReason: {reason}");
+            (
+                ActionKind::Notify,
+                notify(http, db, server_id, "Pool Member", &description)
+                    .await
+                    .map(|_| Outcome::Notified),
+            )
+        }
+    };
+
+    match result {
+        // nothing was done, so there is nothing to log
+        Ok(outcome @ Outcome::Skipped(_)) => Ok(outcome),
+        Ok(outcome) => {
+            info!(
+                "{:?} {} in {} because of {}",
+                outcome, user_id, server_id, cause
+            );
+            record(db, server_id, user_id, cause.name(), kind, Ok(&outcome)).await;
+            Ok(outcome)
+        }
+        Err(e) if is_not_found(&e) => {
+            Ok(Outcome::Skipped(String::from("not a member of this guild")))
+        }
+        // retrying won't help and would log a failure for every attempt
+        Err(e) if e.is::<NotConfigured>() => Ok(Outcome::Skipped(e.to_string())),
+        Err(e) => {
+            error!(
+                "Failed to enforce {} against {} in {}: {}",
+                cause, user_id, server_id, e
+            );
+            record(db, server_id, user_id, cause.name(), kind, Err(&e)).await;
+            Err(e)
+        }
+    }
+}
+
+/// Bans a user, purging as many days of their messages as the ban asks for or
+/// the guild is configured to
+async fn ban(
+    http: &serenity::Http,
+    db: &DB,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+    delete_message_days: Option<u8>,
+    audit_reason: &str,
+) -> Result<Outcome> {
+    let delete_message_days = match delete_message_days {
+        Some(days) => days,
+        None => db
+            .get_server_config(&guild_id.to_string())
+            .await?
+            .and_then(|config| config.delete_message_days)
+            .unwrap_or(0),
+    };
+
+    // re-banning would pass the guild's own ban off as the pool's, and later
+    // lift it when the user leaves the pool
+    if guild_ban(http, guild_id, user_id).await?.is_some() {
+        return Ok(Outcome::Skipped(String::from("already banned here")));
+    }
+
+    guild_id
+        .ban_with_reason(http, user_id, delete_message_days, audit_reason)
+        .await?;
+    Ok(Outcome::Banned)
+}
+
+/// Swaps a member's roles for the guild's quarantine role, remembering the
+/// roles that were taken away
+async fn quarantine(
+    http: &serenity::Http,
+    db: &DB,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+    pool_name: &str,
+) -> Result<Outcome> {
+    let server_id = guild_id.to_string();
+    let quarantine_role = db.list_quarantine_role(&server_id).await.map_err(|e| {
+        // a missing role is for the guild to fix, database errors are retried
+        if e.is::<mongodb::error::Error>() {
+            e
+        } else {
+            NotConfigured(e.to_string()).into()
+        }
+    })?;
+    let role_id = serenity::RoleId(quarantine_role.role_id.parse::<u64>()?);
+
+    let member = guild_id.member(http, user_id).await?;
+    let guild_roles = guild_id.roles(http).await?;
+    // managed roles belong to integrations and boosts, they can't be taken away
+    let (mut roles, removed): (Vec<serenity::RoleId>, Vec<serenity::RoleId>) = member
+        .roles
+        .iter()
+        .copied()
+        .filter(|role| *role != role_id)
+        .partition(|role| guild_roles.get(role).is_some_and(|role| role.managed));
+
+    // keep the original roles if another pool already quarantined the member
+    let already_quarantined = db
+        .get_quarantined_member(&server_id, &user_id.to_string())
+        .await?
+        .is_some();
+
+    roles.push(role_id);
+    guild_id
+        .edit_member(http, user_id, |m| m.roles(roles))
+        .await?;
+    // only recorded once the roles were actually swapped
+    if !already_quarantined {
+        db.add_quarantined_member(
+            &server_id,
+            &user_id.to_string(),
+            pool_name,
+            removed.iter().map(|role| role.to_string()).collect(),
+        )
+        .await?;
+    }
+    Ok(Outcome::Quarantined)
+}
+
+/// Puts a member in a timeout for the guild's configured duration, giving
+/// moderators time to review them
+async fn timeout(
+    http: &serenity::Http,
+    db: &DB,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+) -> Result<Outcome> {
+    let minutes = db
+        .get_server_config(&guild_id.to_string())
+        .await?
+        .and_then(|config| config.timeout_minutes)
+        .unwrap_or(DEFAULT_TIMEOUT_MINUTES);
+    let until = Utc::now() + Duration::minutes(minutes);
+    let timestamp = serenity::Timestamp::from_unix_timestamp(until.timestamp())?;
+
+    guild_id
+        .edit_member(http, user_id, |m| {
+            m.disable_communication_until_datetime(timestamp)
+        })
+        .await?;
+    Ok(Outcome::TimedOut(until))
+}
+
+/// Gives a quarantined member their roles back once no subscribed pool calls
+/// for the quarantine anymore
+async fn release_in_guild(
+    http: &serenity::Http,
+    db: &DB,
+    quarantine: QuarantinedMember,
+    user_id: serenity::UserId,
+    remaining_pools: &[String],
+) -> Result<Outcome> {
+    let server_id = quarantine.server_id.as_str();
+    for subscription in db.list_active_subscriptions(server_id).await? {
+        if subscription.subscription_level == SubscriptionLevel::Quarantine
+            && remaining_pools.contains(&subscription.pool_name)
+        {
+            return Ok(Outcome::Skipped(format!(
+                "still quarantined through `{}`",
+                subscription.pool_name
+            )));
+        }
+    }
+
+    if db.is_shadow_mode(server_id).await {
+        return Ok(shadow(
+            http,
+            db,
+            server_id,
+            user_id,
+            Cause::Pool(&quarantine.pool_name),
+            ActionKind::Release,
+            "the user was removed from the pool",
+        )
+        .await);
+    }
+
+    let guild_id = serenity::GuildId(server_id.parse::<u64>()?);
+    let member = match guild_id.member(http, user_id).await {
+        Ok(member) => member,
+        Err(e) => {
+            let e = anyhow::Error::from(e);
+            if !is_not_found(&e) {
+                return Err(e);
+            }
+            db.delete_quarantined_member(server_id, &user_id.to_string())
+                .await?;
+            return Ok(Outcome::Skipped(String::from(
+                "no longer a member of this guild",
+            )));
+        }
+    };
+
+    let quarantine_role = db
+        .list_quarantine_role(server_id)
+        .await
+        .ok()
+        .and_then(|role| role.role_id.parse::<u64>().ok())
+        .map(serenity::RoleId);
+    let guild_roles = guild_id.roles(http).await?;
+    let mut roles: Vec<serenity::RoleId> = member
+        .roles
+        .iter()
+        .copied()
+        .filter(|role| Some(*role) != quarantine_role)
+        .collect();
+    // roles deleted while the member was quarantined can't be given back
+    for role in quarantine.removed_roles.iter() {
+        if let Ok(role) = role.parse::<u64>().map(serenity::RoleId) {
+            if guild_roles.contains_key(&role) && !roles.contains(&role) {
+                roles.push(role);
+            }
+        }
+    }
+
+    guild_id
+        .edit_member(http, user_id, |m| m.roles(roles))
+        .await?;
+    db.delete_quarantined_member(server_id, &user_id.to_string())
+        .await?;
+    info!("Released {} from quarantine in {}", user_id, server_id);
+    Ok(Outcome::Released)
+}
+
+/// Reports what enforcement would have done in a guild running in shadow mode
+async fn shadow(
+    http: &serenity::Http,
+    db: &DB,
+    server_id: &str,
+    user_id: serenity::UserId,
+    cause: Cause<'_>,
+    kind: ActionKind,
+    reason: &str,
+) -> Outcome {
+    let verb = match kind {
+        ActionKind::Ban => "banned",
+        ActionKind::Kick => "kicked",
+        ActionKind::Notify => "notified about",
+        ActionKind::Unban => "unbanned",
+        ActionKind::Quarantine => "quarantined",
+        ActionKind::Release => "released",
+        ActionKind::Timeout => "timed out",
+    };
+    info!(
+        "Shadow mode: would have {} {} in {} because of {}",
+        verb, user_id, server_id, cause
+    );
+
+    let description = format!(
+        "Would have {verb} <@{user_id}> (`{user_id}`) because of {cause}.\nReason: {reason}"
+    );
+    if let Err(e) = notify(http, db, server_id, "Shadow Mode", &description).await {
+        error!("Unable to post shadow notice to {}: {}", server_id, e);
+    }
+    Outcome::Shadowed(kind)
+}
+
+/// Writes an enforcement attempt to the action log
+async fn record(
+    db: &DB,
+    server_id: &str,
+    user_id: serenity::UserId,
+    pool_name: &str,
+    kind: ActionKind,
+    result: Result<&Outcome, &anyhow::Error>,
+) {
+    let (error, expires_at) = match result {
+        Ok(Outcome::TimedOut(until)) => (None, Some(*until)),
+        Ok(_) => (None, None),
+        Err(e) => (Some(e.to_string()), None),
+    };
+    if let Err(e) = db
+        .add_action(
+            server_id,
+            &user_id.to_string(),
+            pool_name,
+            kind,
+            error,
+            expires_at,
+        )
+        .await
+    {
+        error!(
+            "Unable to record {:?} of {} in {}: {}",
+            kind, user_id, server_id, e
+        );
+    }
+}
+
+/// Enforces a pool ban in a single subscribed guild. The subscription, the
+/// guild's exceptions and the pool itself are checked again, as they may have
+/// changed since the work was queued.
+pub async fn enforce_in_guild(
+    http: &serenity::Http,
+    db: &DB,
+    server_id: &str,
+    user_id: &str,
+    pool_name: &str,
+    reason: &str,
+) -> Result<Outcome> {
+    let target = serenity::UserId(user_id.parse::<u64>()?);
+    let subscription = match db.get_subscription(pool_name, server_id).await {
+        Ok(subscription) => subscription,
+        Err(_) => return Ok(Outcome::Skipped(String::from("no longer subscribed"))),
+    };
+    if db.is_user_exception(server_id, user_id).await {
+        return Ok(Outcome::Skipped(String::from(
+            "user is excepted in this guild",
+        )));
+    }
+    let ban = match db.get_ban_from_pool(user_id, pool_name).await?.pop() {
+        Some(ban) => ban,
+        None => return Ok(Outcome::Skipped(String::from("no longer in the pool"))),
+    };
+
+    enforce(
+        http,
+        db,
+        &subscription,
+        target,
+        reason,
+        ban.delete_message_days,
+    )
+    .await
+}
+
+/// Lifts a guild ban or quarantine that was applied because of a pool ban
+/// which has since been removed. Bans the guild issued itself, or that another
+/// subscribed pool still calls for, are left in place.
+pub async fn lift_in_guild(
+    http: &serenity::Http,
+    db: &DB,
+    server_id: &str,
+    user_id: &str,
+    pool_name: &str,
+) -> Result<Outcome> {
+    let target = serenity::UserId(user_id.parse::<u64>()?);
+    let remaining_pools: Vec<String> = db
+        .get_user_bans(user_id)
+        .await?
+        .into_iter()
+        // expired bans are only kept until lifting them has been queued
+        .filter(|ban| ban.expires_at.is_none_or(|at| at > Utc::now()))
+        .map(|ban| ban.pool_name)
+        .collect();
+
+    // the pool's own last action decides what there is to lift, the member may
+    // have been quarantined through a different pool
+    let quarantined = db
+        .last_action(
+            server_id,
+            user_id,
+            pool_name,
+            &[
+                ActionKind::Ban,
+                ActionKind::Unban,
+                ActionKind::Quarantine,
+                ActionKind::Release,
+            ],
+        )
+        .await?
+        .is_some_and(|action| action.action == ActionKind::Quarantine);
+    let (kind, result) = if quarantined {
+        match db.get_quarantined_member(server_id, user_id).await? {
+            Some(quarantine) => (
+                ActionKind::Release,
+                release_in_guild(http, db, quarantine, target, &remaining_pools).await,
+            ),
+            None => {
+                return Ok(Outcome::Skipped(String::from(
+                    "no longer quarantined in this guild",
+                )))
+            }
+        }
+    } else {
+        (
+            ActionKind::Unban,
+            unban_in_guild(http, db, server_id, target, pool_name, &remaining_pools).await,
+        )
+    };
+
+    match result {
+        Ok(outcome @ (Outcome::Unbanned | Outcome::Released)) => {
+            record(db, server_id, target, pool_name, kind, Ok(&outcome)).await;
+            Ok(outcome)
+        }
+        Ok(outcome) => Ok(outcome),
+        Err(e) => {
+            error!(
+                "Failed to lift {} {:?} on {} in {}: {}",
+                pool_name, kind, user_id, server_id, e
+            );
+            record(db, server_id, target, pool_name, kind, Err(&e)).await;
+            Err(e)
+        }
+    }
+}
+
+/// Adds a guild's own Discord bans to a pool and queues them for every other
+/// subscribed guild. Bans the bot issued on behalf of any pool are left out,
+/// so users removed from this pool are not added back and other pools' bans
+/// don't spread through this one. Returns the IDs of the users that were added.
+pub async fn import_guild_bans(
+    db: &DB,
+    pool_name: &str,
+    guild_bans: &[serenity::Ban],
+    editor: &PoolEditor,
+) -> Result<Vec<String>> {
+    let now = Utc::now();
+    let bans = guild_bans
+        .iter()
+        .filter(|ban| !issued_by_bot(ban))
+        .map(|ban| Ban {
+            user_id: ban.user.id.to_string(),
+            pool_name: pool_name.to_string(),
+            reason: ban
+                .reason
+                .clone()
+                .unwrap_or_else(|| String::from("No reason given")),
+            creator_id: editor.user_id.clone(),
+            expires_at: None,
+            delete_message_days: None,
+            username: Some(ban.user.name.clone()),
+            discriminator: Some(ban.user.discriminator),
+            avatar_url: ban.user.avatar_url(),
+            timestamp: now,
+        })
+        .collect();
+
+    add_pool_bans(db, pool_name, editor, bans, Some(&editor.server_id)).await
+}
+
+/// Adds many bans to a pool in one batch, skipping users already in it, and
+/// queues the new ones for every subscribed guild except the one the bans came
+/// from, if any. Returns the IDs of the users that were added.
+pub async fn add_pool_bans(
+    db: &DB,
+    pool_name: &str,
+    editor: &PoolEditor,
+    bans: Vec<Ban>,
+    source_server: Option<&str>,
+) -> Result<Vec<String>> {
+    let mut reasons: HashMap<String, String> = bans
+        .iter()
+        .map(|ban| (ban.user_id.clone(), ban.reason.clone()))
+        .collect();
+
+    let added = db.add_bans(pool_name, editor, bans).await?;
+    let users: Vec<(String, String)> = added
+        .iter()
+        .map(|user_id| {
+            let reason = reasons.remove(user_id).unwrap_or_default();
+            (user_id.clone(), reason)
+        })
+        .collect();
+    queue::enqueue_pool_users(db, JobKind::Enforce, pool_name, &users, source_server).await?;
+    info!("Added {} bans to {}", added.len(), pool_name);
+    Ok(added)
+}
+
+/// Whether the user's current ban in the guild was issued because of the pool.
+/// Only a ban carrying the pool's audit log reason counts, so a guild's own
+/// ban is never mistaken for the pool's. The action log must not show that the
+/// pool's ban was already lifted either.
+async fn banned_by_pool(
+    http: &serenity::Http,
+    db: &DB,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+    pool_name: &str,
+) -> Result<bool> {
+    let prefix = audit_prefix(pool_name);
+    let issued_by_pool = guild_ban(http, guild_id, user_id)
+        .await?
+        .is_some_and(|ban| {
+            ban.reason
+                .as_deref()
+                .is_some_and(|reason| reason.starts_with(&prefix))
+        });
+    if !issued_by_pool {
+        return Ok(false);
+    }
+
+    let last_action = db
+        .last_action(
+            &guild_id.to_string(),
+            &user_id.to_string(),
+            pool_name,
+            &[ActionKind::Ban, ActionKind::Unban],
+        )
+        .await?;
+    Ok(last_action.is_none_or(|action| action.action == ActionKind::Ban))
+}
+
+async fn unban_in_guild(
+    http: &serenity::Http,
+    db: &DB,
+    server_id: &str,
+    user_id: serenity::UserId,
+    pool_name: &str,
+    remaining_pools: &[String],
+) -> Result<Outcome> {
+    let guild_id = serenity::GuildId(server_id.parse::<u64>()?);
+
+    if !banned_by_pool(http, db, guild_id, user_id, pool_name).await? {
+        return Ok(Outcome::Skipped(String::from(
+            "not banned here because of this pool",
+        )));
+    }
+
+    for subscription in db.list_active_subscriptions(server_id).await? {
+        if subscription.subscription_level == SubscriptionLevel::AutoBan
+            && remaining_pools.contains(&subscription.pool_name)
+        {
+            return Ok(Outcome::Skipped(format!(
+                "still banned through `{}`",
+                subscription.pool_name
+            )));
+        }
+    }
+
+    if db.is_shadow_mode(server_id).await {
+        return Ok(shadow(
+            http,
+            db,
+            server_id,
+            user_id,
+            Cause::Pool(pool_name),
+            ActionKind::Unban,
+            "the user was removed from the pool",
+        )
+        .await);
+    }
+
+    match guild_id.unban(http, user_id).await {
+        Ok(_) => {
+            info!("Unbanned {} in {}", user_id, server_id);
+            Ok(Outcome::Unbanned)
+        }
+        Err(e) => {
+            let e = anyhow::Error::from(e);
+            if is_not_found(&e) {
+                Ok(Outcome::Skipped(String::from(
+                    "no longer banned in this guild",
+                )))
+            } else {
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Checks a member joining a guild against every pool the guild subscribes to
+/// and applies the matching subscription's level.
+pub async fn enforce_on_join(
+    http: &serenity::Http,
+    db: &DB,
+    member: &serenity::Member,
+) -> Result<()> {
+    let server_id = member.guild_id.to_string();
+    let user_id = member.user.id.to_string();
+
+    if db.is_user_exception(&server_id, &user_id).await {
+        return Ok(());
+    }
+
+    if let Some(Outcome::Banned | Outcome::Kicked | Outcome::Quarantined | Outcome::TimedOut(_)) =
+        gate_account_age(http, db, member).await?
+    {
+        return Ok(());
+    }
+
+    for subscription in db.list_active_subscriptions(&server_id).await? {
+        let bans = db
+            .get_ban_from_pool(&user_id, &subscription.pool_name)
+            .await?;
+        let ban = match bans.first() {
+            Some(ban) => ban,
+            None => continue,
+        };
+
+        info!(
+            "{} joined {} and is in pool {}",
+            user_id, server_id, subscription.pool_name
+        );
+        match apply_level(
+            http,
+            db,
+            &subscription,
+            member.user.id,
+            &ban.reason,
+            ban.delete_message_days,
+        )
+        .await
+        {
+            // the member is gone or isolated, no need to check the remaining pools
+            Outcome::Banned | Outcome::Kicked | Outcome::Quarantined | Outcome::TimedOut(_) => {
+                return Ok(())
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies the guild's account age action to a member whose account, going by
+/// the timestamp in their ID, is younger than the configured minimum
+async fn gate_account_age(
+    http: &serenity::Http,
+    db: &DB,
+    member: &serenity::Member,
+) -> Result<Option<Outcome>> {
+    let server_id = member.guild_id.to_string();
+    let config = match db.get_server_config(&server_id).await? {
+        Some(config) => config,
+        None => return Ok(None),
+    };
+    let min_days = match config.min_account_age_days {
+        Some(days) if days > 0 => days,
+        _ => return Ok(None),
+    };
+    let created_at = member.user.id.created_at().unix_timestamp();
+    let age_days = (Utc::now().timestamp() - created_at) / (60 * 60 * 24);
+    if age_days >= min_days {
+        return Ok(None);
+    }
+
+    let user_id = member.user.id;
+    let reason = format!("Account is {age_days} days old, the minimum is {min_days}");
+    let outcome = match config.account_age_action {
+        Some(level) if level != SubscriptionLevel::NotifyOnly => {
+            let gate = Enforcement {
+                server_id: &server_id,
+                level,
+                cause: Cause::AccountAge,
+                delete_message_days: None,
+            };
+            act(http, db, &gate, user_id, &reason)
+                .await
+                .unwrap_or_else(|e| Outcome::Failed(e.to_string()))
+        }
+        _ => Outcome::Notified,
+    };
+
+    info!(
+        "{} joined {} with a new account: {}",
+        user_id, server_id, outcome
+    );
+    let description = format!(
+        "<@{user_id}> (`{user_id}`) joined with a new account.\n{reason}\nOutcome: {outcome}"
+    );
+    if let Err(e) = notify(http, db, &server_id, "New Account", &description).await {
+        error!(
+            "Unable to report new account {} in {}: {}",
+            user_id, server_id, e
+        );
+    }
+    Ok(Some(outcome))
+}
+
+/// Pages through a guild's member list looking for members that are already
+/// in a pool the guild subscribes to.
+pub struct MemberSweep {
+    subscription: Subscription,
+    pool_bans: HashMap<String, Ban>,
+    after: Option<serenity::UserId>,
+    done: bool,
+    pub scanned: usize,
+    pub matches: Vec<(String, Outcome)>,
+}
+
+impl MemberSweep {
+    pub async fn new(db: &DB, subscription: Subscription) -> Result<Self> {
+        let pool_bans = db
+            .get_pool_bans(&subscription.pool_name)
+            .await?
+            .into_iter()
+            .map(|ban| (ban.user_id.clone(), ban))
+            .collect();
+
+        Ok(Self {
+            subscription,
+            pool_bans,
+            after: None,
+            done: false,
+            scanned: 0,
+            matches: Vec::new(),
+        })
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Checks the next page of members. Matches are recorded as `Notified`
+    /// unless `apply` is set, in which case the subscription level is enforced.
+    pub async fn next_page(&mut self, http: &serenity::Http, db: &DB, apply: bool) -> Result<()> {
+        let guild_id = serenity::GuildId(self.subscription.server_id.parse::<u64>()?);
+        let members = guild_id
+            .members(http, Some(MEMBER_PAGE_SIZE), self.after)
+            .await?;
+
+        self.done = (members.len() as u64) < MEMBER_PAGE_SIZE;
+        self.after = members.last().map(|member| member.user.id);
+        self.scanned += members.len();
+
+        for member in members {
+            let user_id = member.user.id.to_string();
+            let ban = match self.pool_bans.get(&user_id) {
+                Some(ban) => ban,
+                None => continue,
+            };
+
+            let outcome = if db
+                .is_user_exception(&self.subscription.server_id, &user_id)
+                .await
+            {
+                Outcome::Skipped(String::from("user is excepted in this guild"))
+            } else if apply {
+                apply_level(
+                    http,
+                    db,
+                    &self.subscription,
+                    member.user.id,
+                    &ban.reason,
+                    ban.delete_message_days,
+                )
+                .await
+            } else {
+                Outcome::Notified
+            };
+            self.matches.push((user_id, outcome));
+        }
+
+        Ok(())
+    }
+
+    /// Posts the members found by the sweep to the guild's notification channel
+    pub async fn report(&self, http: &serenity::Http, db: &DB) -> Result<()> {
+        let mut report = format!(
+            "Scanned {} members, {} are in the `{}` pool:\n",
+            self.scanned,
+            self.matches.len(),
+            self.subscription.pool_name
+        );
+        for (shown, (user_id, _)) in self.matches.iter().enumerate() {
+            let reason = self
+                .pool_bans
+                .get(user_id)
+                .map_or("", |ban| ban.reason.as_str());
+            let line = format!("<@{user_id}> (`{user_id}`): {reason}\n");
+            if report.len() + line.len() > MAX_REPORT_LENGTH {
+                report.push_str(&format!("...and {} more", self.matches.len() - shown));
+                break;
+            }
+            report.push_str(&line);
+        }
+
+        notify(
+            http,
+            db,
+            &self.subscription.server_id,
+            "Pool Member Sweep",
+            &report,
+        )
+        .await
+    }
+}
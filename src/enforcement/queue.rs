@@ -0,0 +1,236 @@
+use super::{enforce_in_guild, lift_in_guild, status_code};
+use crate::db::mongo::{EnforcementJob, JobKind, JobStatus, DB};
+use anyhow::{anyhow, Result};
+use chrono::{Duration, Utc};
+use log::{error, info, warn};
+use poise::serenity_prelude as serenity;
+use std::sync::Arc;
+
+// How long the worker sleeps when there is nothing due
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+// Delay before the first retry of a failed job, doubled on every attempt
+const RETRY_BASE_SECS: i64 = 30;
+// Jobs that keep failing are given up on after this many attempts
+const MAX_ATTEMPTS: u32 = 5;
+// Minimum wait once Discord has answered with 429 Too Many Requests
+const RATE_LIMIT_SECS: i64 = 60;
+
+fn new_job(
+    kind: JobKind,
+    server_id: String,
+    user_id: &str,
+    pool_name: &str,
+    reason: &str,
+) -> EnforcementJob {
+    let now = Utc::now();
+    EnforcementJob {
+        id: None,
+        kind,
+        server_id,
+        user_id: user_id.to_string(),
+        pool_name: pool_name.to_string(),
+        reason: reason.to_string(),
+        status: JobStatus::Pending,
+        attempts: 0,
+        last_error: None,
+        next_attempt: now,
+        timestamp: now,
+    }
+}
+
+/// Queues a job for every guild subscribed to the pool and returns the IDs of
+/// those guilds
+pub async fn enqueue_pool(
+    db: &DB,
+    kind: JobKind,
+    user_id: &str,
+    pool_name: &str,
+    reason: &str,
+) -> Result<Vec<String>> {
+    user_id
+        .parse::<u64>()
+        .map_err(|_| anyhow!("`{user_id}` is not a valid Discord user ID"))?;
+
+    let jobs: Vec<EnforcementJob> = db
+        .list_subscribed_servers(pool_name)
+        .await?
+        .into_iter()
+        .map(|subscription| new_job(kind, subscription.server_id, user_id, pool_name, reason))
+        .collect();
+
+    let servers = jobs.iter().map(|job| job.server_id.clone()).collect();
+    db.add_jobs(jobs).await?;
+    info!("Queued {:?} of {} from {}", kind, user_id, pool_name);
+    Ok(servers)
+}
+
+/// Queues a job for each of many pool members in every guild subscribed to the
+/// pool, in one batch. Takes the users' IDs with the reasons of their bans, and
+/// the guild they were taken from, which already banned them.
+pub async fn enqueue_pool_users(
+    db: &DB,
+    kind: JobKind,
+    pool_name: &str,
+    users: &[(String, String)],
+    source_server: Option<&str>,
+) -> Result<()> {
+    let subscriptions: Vec<_> = db
+        .list_subscribed_servers(pool_name)
+        .await?
+        .into_iter()
+        .filter(|subscription| Some(subscription.server_id.as_str()) != source_server)
+        .collect();
+    let mut jobs = Vec::new();
+    for (user_id, reason) in users {
+        user_id
+            .parse::<u64>()
+            .map_err(|_| anyhow!("`{user_id}` is not a valid Discord user ID"))?;
+        for subscription in &subscriptions {
+            jobs.push(new_job(
+                kind,
+                subscription.server_id.clone(),
+                user_id,
+                pool_name,
+                reason,
+            ));
+        }
+    }
+
+    db.add_jobs(jobs).await?;
+    info!(
+        "Queued {:?} of {} users from {} in {} guilds",
+        kind,
+        users.len(),
+        pool_name,
+        subscriptions.len()
+    );
+    Ok(())
+}
+
+/// Queues a job for a single guild unless an identical one is already queued.
+/// Returns whether a job was added.
+pub async fn enqueue_guild(
+    db: &DB,
+    kind: JobKind,
+    server_id: &str,
+    user_id: &str,
+    pool_name: &str,
+    reason: &str,
+) -> Result<bool> {
+    if db.has_open_job(kind, server_id, user_id, pool_name).await? {
+        return Ok(false);
+    }
+
+    let job = new_job(kind, server_id.to_string(), user_id, pool_name, reason);
+    db.add_jobs(vec![job]).await?;
+    info!(
+        "Queued {:?} of {} from {} in {}",
+        kind, user_id, pool_name, server_id
+    );
+    Ok(true)
+}
+
+/// Works through the enforcement queue for as long as the bot is running
+pub async fn run_worker(http: Arc<serenity::Http>) {
+    let db = match DB::init().await {
+        Ok(db) => db,
+        Err(e) => {
+            error!(
+                "Enforcement worker could not connect to the database: {}",
+                e
+            );
+            return;
+        }
+    };
+
+    match db.requeue_running_jobs().await {
+        Ok(0) => {}
+        Ok(count) => info!("Requeued {} interrupted enforcement jobs", count),
+        Err(e) => error!("Unable to requeue interrupted enforcement jobs: {}", e),
+    }
+
+    loop {
+        match db.claim_job().await {
+            Ok(Some(job)) => run_job(&http, &db, job).await,
+            Ok(None) => tokio::time::sleep(POLL_INTERVAL).await,
+            Err(e) => {
+                error!("Unable to claim an enforcement job: {}", e);
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+async fn run_job(http: &serenity::Http, db: &DB, job: EnforcementJob) {
+    let id = match job.id {
+        Some(id) => id,
+        None => return,
+    };
+
+    let result = match job.kind {
+        JobKind::Enforce => {
+            enforce_in_guild(
+                http,
+                db,
+                &job.server_id,
+                &job.user_id,
+                &job.pool_name,
+                &job.reason,
+            )
+            .await
+        }
+        JobKind::Lift => {
+            lift_in_guild(http, db, &job.server_id, &job.user_id, &job.pool_name).await
+        }
+    };
+
+    let update = match result {
+        Ok(outcome) => {
+            info!(
+                "{:?} job for {} in {} finished: {}",
+                job.kind, job.user_id, job.server_id, outcome
+            );
+            db.complete_job(id).await
+        }
+        Err(e) => match retry_delay(&e, job.attempts) {
+            Some(delay) => {
+                warn!(
+                    "{:?} job for {} in {} failed, retrying in {}s: {}",
+                    job.kind,
+                    job.user_id,
+                    job.server_id,
+                    delay.num_seconds(),
+                    e
+                );
+                db.retry_job(id, Utc::now() + delay, &e.to_string()).await
+            }
+            None => {
+                error!(
+                    "{:?} job for {} in {} failed for good: {}",
+                    job.kind, job.user_id, job.server_id, e
+                );
+                db.fail_job(id, &e.to_string()).await
+            }
+        },
+    };
+
+    if let Err(e) = update {
+        error!("Unable to update enforcement job {}: {}", id, e);
+    }
+}
+
+/// How long to wait before retrying a failed job, or `None` if it should not
+/// be retried. Rate limited jobs are always retried; other client errors such
+/// as missing permissions will not fix themselves.
+fn retry_delay(e: &anyhow::Error, attempts: u32) -> Option<Duration> {
+    let backoff = Duration::seconds(RETRY_BASE_SECS << attempts.saturating_sub(1).min(10));
+
+    match status_code(e) {
+        Some(serenity::StatusCode::TOO_MANY_REQUESTS) => {
+            Some(backoff.max(Duration::seconds(RATE_LIMIT_SECS)))
+        }
+        Some(status) if status.is_client_error() => None,
+        _ if attempts >= MAX_ATTEMPTS => None,
+        _ => Some(backoff),
+    }
+}
@@ -0,0 +1,213 @@
+use super::queue::enqueue_guild;
+use super::{audit_prefix, guild_bans, list_users, notify, MAX_REPORT_LENGTH};
+use crate::db::mongo::{Ban, JobKind, Subscription, SubscriptionLevel, DB};
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use log::{error, info};
+use poise::serenity_prelude as serenity;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+// How often every subscription is checked against its guild's ban list
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+// Repairs that failed for good are not queued again until this many days later
+const REPAIR_BACKOFF_DAYS: i64 = 7;
+
+/// The differences between a pool and a subscribed guild's Discord ban list
+pub struct Drift {
+    // in the pool but not banned in the guild
+    pub missing: Vec<Ban>,
+    // in the pool but excepted in the guild
+    pub excepted: Vec<Ban>,
+    // banned in the guild but not in the pool
+    pub unpooled: Vec<serenity::Ban>,
+}
+
+impl Drift {
+    /// Guild bans issued on behalf of the pool for users that have since left it
+    pub fn stale(&self, pool_name: &str) -> Vec<&serenity::Ban> {
+        let prefix = audit_prefix(pool_name);
+        self.unpooled
+            .iter()
+            .filter(|ban| {
+                ban.reason
+                    .as_deref()
+                    .is_some_and(|reason| reason.starts_with(&prefix))
+            })
+            .collect()
+    }
+}
+
+/// Compares a pool's bans with the current ban list of a subscribed guild
+pub async fn find_drift(
+    http: &serenity::Http,
+    db: &DB,
+    server_id: &str,
+    pool_name: &str,
+) -> Result<Drift> {
+    // private pools only share their bans with guilds that were let in
+    if !db.get_pool(pool_name).await?.can_subscribe(server_id) {
+        return Err(anyhow!("{pool_name} is private"));
+    }
+
+    let guild_id = serenity::GuildId(server_id.parse::<u64>()?);
+    let guild_bans = guild_bans(http, guild_id).await?;
+    let banned: HashSet<String> = guild_bans
+        .iter()
+        .map(|ban| ban.user.id.to_string())
+        .collect();
+    let exceptions: HashSet<String> = db
+        .list_exceptions(server_id)
+        .await?
+        .into_iter()
+        .map(|exception| exception.user_id)
+        .collect();
+
+    let pool_bans = db.get_pool_bans(pool_name).await?;
+    let pooled: HashSet<String> = pool_bans.iter().map(|ban| ban.user_id.clone()).collect();
+
+    let mut drift = Drift {
+        missing: Vec::new(),
+        excepted: Vec::new(),
+        unpooled: guild_bans
+            .into_iter()
+            .filter(|ban| !pooled.contains(&ban.user.id.to_string()))
+            .collect(),
+    };
+    for ban in pool_bans {
+        if exceptions.contains(&ban.user_id) {
+            drift.excepted.push(ban);
+        } else if !banned.contains(&ban.user_id) {
+            drift.missing.push(ban);
+        }
+    }
+
+    Ok(drift)
+}
+
+/// Periodically checks every auto-ban subscription for drift between the pool
+/// and the guild's ban list, for as long as the bot is running
+pub async fn run_reconciler(http: Arc<serenity::Http>) {
+    let db = match DB::init().await {
+        Ok(db) => db,
+        Err(e) => {
+            error!("Reconciler could not connect to the database: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        tokio::time::sleep(RECONCILE_INTERVAL).await;
+
+        let subscriptions = match db.list_all_subscriptions().await {
+            Ok(subscriptions) => subscriptions,
+            Err(e) => {
+                error!("Unable to list subscriptions to reconcile: {}", e);
+                continue;
+            }
+        };
+        for subscription in subscriptions {
+            // only bans show up in a guild's ban list
+            if subscription.subscription_level != SubscriptionLevel::AutoBan {
+                continue;
+            }
+            if let Err(e) = reconcile(&http, &db, &subscription).await {
+                error!(
+                    "Unable to reconcile {} with {}: {}",
+                    subscription.pool_name, subscription.server_id, e
+                );
+            }
+        }
+    }
+}
+
+/// Repairs or reports the drift of a single subscription, depending on the
+/// guild's configuration
+async fn reconcile(http: &serenity::Http, db: &DB, subscription: &Subscription) -> Result<()> {
+    let server_id = &subscription.server_id;
+    let pool_name = &subscription.pool_name;
+    let drift = find_drift(http, db, server_id, pool_name).await?;
+    let stale = drift.stale(pool_name);
+    if drift.missing.is_empty() && stale.is_empty() {
+        return Ok(());
+    }
+
+    info!(
+        "{} has drifted from {}: {} missing, {} stale bans",
+        server_id,
+        pool_name,
+        drift.missing.len(),
+        stale.len()
+    );
+
+    // shadow mode guilds would get a fresh notice per user on every cycle
+    let repair = db
+        .get_server_config(server_id)
+        .await?
+        .is_some_and(|config| config.reconcile_repair && !config.shadow_mode);
+    if repair {
+        for ban in &drift.missing {
+            queue_repair(
+                db,
+                JobKind::Enforce,
+                server_id,
+                &ban.user_id,
+                pool_name,
+                &ban.reason,
+            )
+            .await?;
+        }
+        for ban in &stale {
+            queue_repair(
+                db,
+                JobKind::Lift,
+                server_id,
+                &ban.user.id.to_string(),
+                pool_name,
+                "",
+            )
+            .await?;
+        }
+        return Ok(());
+    }
+
+    let missing: Vec<String> = drift
+        .missing
+        .iter()
+        .map(|ban| ban.user_id.clone())
+        .collect();
+    let stale: Vec<String> = stale.iter().map(|ban| ban.user.id.to_string()).collect();
+    let mut report = format!("This server's bans have drifted from the `{pool_name}` pool.\n");
+    if !missing.is_empty() {
+        report.push_str("\n**In the pool but not banned here:**\n");
+        report.push_str(&list_users(&missing, MAX_REPORT_LENGTH / 2));
+    }
+    if !stale.is_empty() {
+        report.push_str("\n**Banned by the pool but no longer in it:**\n");
+        report.push_str(&list_users(&stale, MAX_REPORT_LENGTH / 2));
+    }
+
+    notify(http, db, server_id, "Pool Drift", &report).await
+}
+
+/// Queues a repair unless the same job recently failed for good, e.g. because
+/// the bot lacks the permission, as retrying it every cycle won't help
+async fn queue_repair(
+    db: &DB,
+    kind: JobKind,
+    server_id: &str,
+    user_id: &str,
+    pool_name: &str,
+    reason: &str,
+) -> Result<()> {
+    let since = Utc::now() - chrono::Duration::days(REPAIR_BACKOFF_DAYS);
+    if db
+        .has_failed_job(kind, server_id, user_id, pool_name, since)
+        .await?
+    {
+        return Ok(());
+    }
+    enqueue_guild(db, kind, server_id, user_id, pool_name, reason).await?;
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use super::{import_guild_bans, notification_channel};
+use crate::commands::checks::{has_admin_access, pool_editor};
+use crate::db::mongo::{PoolEditor, DB};
+use anyhow::{anyhow, Result};
+use log::info;
+use poise::serenity_prelude as serenity;
+
+// Custom ID prefixes of the components on a submission prompt. The select menu
+// carries the banned user, the button also carries the chosen pool.
+const SELECT_PREFIX: &str = "submit-pool:";
+const BUTTON_PREFIX: &str = "submit-ban:";
+// Discord allows at most 25 options in a select menu
+const MAX_POOL_OPTIONS: usize = 25;
+
+/// Looks up who issued a guild ban and why in the audit log
+async fn find_ban_entry(
+    http: &serenity::Http,
+    guild_id: serenity::GuildId,
+    user_id: serenity::UserId,
+) -> Result<Option<serenity::AuditLogEntry>> {
+    let action = serenity::Action::Member(serenity::MemberAction::BanAdd);
+    let logs = guild_id
+        .audit_logs(http, Some(action.num()), None, None, Some(10))
+        .await?;
+    Ok(logs
+        .entries
+        .into_iter()
+        .find(|entry| entry.target_id == Some(user_id.0)))
+}
+
+/// Prompts the guild's moderators to add a user they banned by hand to one of
+/// the guild's pools
+pub async fn suggest_submission(
+    http: &serenity::Http,
+    db: &DB,
+    guild_id: serenity::GuildId,
+    banned_user: &serenity::User,
+) -> Result<()> {
+    let server_id = guild_id.to_string();
+    // pools the guild itself owns or maintains, regardless of who bans
+    let guild_editor = PoolEditor {
+        server_id: server_id.clone(),
+        user_id: String::new(),
+        role_ids: vec![],
+    };
+    let pools: Vec<String> = db
+        .list_pools()
+        .await?
+        .into_iter()
+        .filter(|pool| pool.can_edit(&guild_editor))
+        .map(|pool| pool.pool_name)
+        .take(MAX_POOL_OPTIONS)
+        .collect();
+    if pools.is_empty() {
+        return Ok(());
+    }
+
+    let entry = match find_ban_entry(http, guild_id, banned_user.id).await? {
+        Some(entry) => entry,
+        None => return Ok(()),
+    };
+    // bans issued by the bot come from pools already
+    let bot_id = http.get_current_user().await?.id;
+    if entry.user_id == bot_id {
+        return Ok(());
+    }
+
+    let reason = entry
+        .reason
+        .unwrap_or_else(|| String::from("No reason given"));
+    let user_id = banned_user.id;
+    notification_channel(db, &server_id)
+        .await?
+        .send_message(http, |m| {
+            m.embed(|e| {
+                e.title("Submit to Banpool")
+                    .color(serenity::Color::ORANGE)
+                    .description(format!(
+                        "<@{}> banned {} (<@{user_id}>). Pick a pool to add them to it.",
+                        entry.user_id,
+                        banned_user.tag()
+                    ))
+                    .field("Reason", &reason, false)
+            })
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_select_menu(|menu| {
+                        menu.custom_id(format!("{SELECT_PREFIX}{user_id}"))
+                            .placeholder("Banpool")
+                            .options(|options| {
+                                for pool in &pools {
+                                    options.create_option(|o| o.label(pool).value(pool));
+                                }
+                                options
+                            })
+                    })
+                })
+                .create_action_row(|row| {
+                    row.create_button(|b| {
+                        b.style(serenity::ButtonStyle::Danger)
+                            .label("Add to pool")
+                            .custom_id(BUTTON_PREFIX)
+                            .disabled(true)
+                    })
+                })
+            })
+        })
+        .await?;
+    info!("Suggested submitting {} from {}", user_id, server_id);
+    Ok(())
+}
+
+/// Adds the user a submission prompt is about to the chosen pool, returning
+/// whether they were not in it yet
+async fn submit_ban(
+    http: &serenity::Http,
+    db: &DB,
+    interaction: &serenity::MessageComponentInteraction,
+    member: &serenity::Member,
+    user_id: &str,
+    pool: &str,
+) -> Result<bool> {
+    let guild_id = interaction
+        .guild_id
+        .ok_or_else(|| anyhow!("Submission prompts only work in guilds"))?;
+    let target = serenity::UserId(user_id.parse::<u64>()?);
+    let reason = interaction
+        .message
+        .embeds
+        .first()
+        .and_then(|embed| embed.fields.iter().find(|field| field.name == "Reason"))
+        .map(|field| field.value.clone());
+    let ban = serenity::Ban {
+        reason,
+        user: target.to_user(http).await?,
+    };
+    let added = import_guild_bans(db, pool, &[ban], &pool_editor(member)).await?;
+    info!("Submitted {} from {} to {}", user_id, guild_id, pool);
+    Ok(!added.is_empty())
+}
+
+/// Handles the components of a submission prompt, ignoring interactions that
+/// belong to something else
+pub async fn handle_interaction(
+    http: &serenity::Http,
+    db: &DB,
+    interaction: &serenity::MessageComponentInteraction,
+) -> Result<()> {
+    let custom_id = &interaction.data.custom_id;
+    if let Some(user_id) = custom_id.strip_prefix(SELECT_PREFIX) {
+        let pool = interaction
+            .data
+            .values
+            .first()
+            .ok_or_else(|| anyhow!("No pool was selected"))?;
+        // remember the choice on the button, the prompt may be answered much later
+        interaction
+            .create_interaction_response(http, |r| {
+                r.kind(serenity::InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|d| {
+                        d.components(|c| {
+                            c.create_action_row(|row| {
+                                row.create_button(|b| {
+                                    b.style(serenity::ButtonStyle::Danger)
+                                        .label(format!("Add to {pool}"))
+                                        .custom_id(format!("{BUTTON_PREFIX}{user_id}:{pool}"))
+                                })
+                            })
+                        })
+                    })
+            })
+            .await?;
+        return Ok(());
+    }
+
+    let (user_id, pool) = match custom_id
+        .strip_prefix(BUTTON_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+    {
+        Some(ids) => ids,
+        None => return Ok(()),
+    };
+
+    let member = match &interaction.member {
+        Some(member) if has_admin_access(db, member).await => member,
+        _ => {
+            interaction
+                .create_interaction_response(http, |r| {
+                    r.interaction_response_data(|d| {
+                        d.ephemeral(true)
+                            .content("You need this server's admin role to submit bans")
+                    })
+                })
+                .await?;
+            return Ok(());
+        }
+    };
+
+    let added = match submit_ban(http, db, interaction, member, user_id, pool).await {
+        Ok(added) => added,
+        Err(e) => {
+            interaction
+                .create_interaction_response(http, |r| {
+                    r.interaction_response_data(|d| {
+                        d.ephemeral(true)
+                            .content(format!("Unable to add <@{user_id}> to `{pool}`:\n{e}"))
+                    })
+                })
+                .await?;
+            return Err(e);
+        }
+    };
+
+    let result = if added {
+        format!("<@{}> added <@{user_id}> to `{pool}`", interaction.user.id)
+    } else {
+        format!("<@{user_id}> is already in `{pool}`")
+    };
+    interaction
+        .create_interaction_response(http, |r| {
+            r.kind(serenity::InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| d.content(result).components(|c| c))
+        })
+        .await?;
+    Ok(())
+}
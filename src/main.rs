@@ -1,13 +1,13 @@
 mod commands;
 pub mod db;
+mod enforcement;
 use commands::*;
 
+use db::mongo::DB;
 use dotenv::dotenv;
-use log4rs;
 use std::env;
 
 use poise::serenity_prelude as serenity;
-use std::time::Duration;
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, Error>;
@@ -40,6 +40,39 @@ async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
     }
 }
 
+async fn event_handler(
+    ctx: &serenity::Context,
+    event: &poise::Event<'_>,
+    _framework: poise::FrameworkContext<'_, Data, Error>,
+    _data: &Data,
+) -> Result<(), Error> {
+    match event {
+        poise::Event::GuildMemberAddition { new_member } => {
+            let db = DB::init().await?;
+            enforcement::enforce_on_join(&ctx.http, &db, new_member).await?;
+        }
+        poise::Event::GuildBanAddition {
+            guild_id,
+            banned_user,
+        } => {
+            let db = DB::init().await?;
+            enforcement::submission::suggest_submission(&ctx.http, &db, *guild_id, banned_user)
+                .await?;
+        }
+        poise::Event::InteractionCreate {
+            interaction: serenity::Interaction::MessageComponent(interaction),
+        } => {
+            let db = DB::init().await?;
+            enforcement::submission::handle_interaction(&ctx.http, &db, interaction).await?;
+            enforcement::approval::handle_interaction(&ctx.http, &db, interaction).await?;
+        }
+        _ => {
+            println!("Got an event in event handler: {:?}", event.name());
+        }
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
     log4rs::init_file("log4rs.yaml", Default::default()).unwrap();
@@ -49,50 +82,55 @@ async fn main() {
     let options = poise::FrameworkOptions {
         commands: vec![
             register(),
+            admin::admin(),
             bans::ban(),
+            config::config(),
             exceptions::exception(),
             notifications::notification(),
             pools::pool(),
+            queue::queue(),
             subscriptions::subscription(),
         ],
         prefix_options: poise::PrefixFrameworkOptions {
             prefix: Some("~".into()),
             ..Default::default()
         },
-        /// The global error handler for all error cases that may occur
+        // The global error handler for all error cases that may occur
         on_error: |error| Box::pin(on_error(error)),
-        /// This code is run before every command
+        // This code is run before every command
         pre_command: |ctx| {
             Box::pin(async move {
                 println!("Executing command: {}", ctx.command().qualified_name);
             })
         },
-        /// This code is run after a command if it was successful (returned Ok)
+        // This code is run after a command if it was successful (returned Ok)
         post_command: |ctx| {
             Box::pin(async move {
                 println!("Executed command: {}", ctx.command().qualified_name);
             })
         },
-        event_handler: |_ctx, event, _framework, _data| {
-            Box::pin(async move {
-                println!("Got an event in event handler: {:?}", event.name());
-                Ok(())
-            })
+        event_handler: |ctx, event, framework, data| {
+            Box::pin(event_handler(ctx, event, framework, data))
         },
         ..Default::default()
     };
 
     poise::Framework::builder()
         .token(token)
-        .setup(move |_ctx, _ready, _framework| {
+        .setup(move |ctx, _ready, _framework| {
             Box::pin(async move {
                 println!("Logged in as {}", _ready.user.name);
+                tokio::spawn(enforcement::queue::run_worker(ctx.http.clone()));
+                tokio::spawn(enforcement::reconcile::run_reconciler(ctx.http.clone()));
+                tokio::spawn(enforcement::expiry::run_expiry());
                 Ok(Data {})
             })
         })
         .options(options)
         .intents(
-            serenity::GatewayIntents::non_privileged() | serenity::GatewayIntents::MESSAGE_CONTENT,
+            serenity::GatewayIntents::non_privileged()
+                | serenity::GatewayIntents::MESSAGE_CONTENT
+                | serenity::GatewayIntents::GUILD_MEMBERS,
         )
         .run()
         .await